@@ -0,0 +1,654 @@
+use bevy::{
+	prelude::*, input::common_conditions::*, input::mouse::MouseWheel, ecs::system::SystemParam
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+use crate::save;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<ActionState>()
+		.init_resource::<Rebinding>()
+		.init_resource::<WheelScroll>()
+		.add_systems(PreStartup, load_controls)
+		.add_systems(Startup, setup_ui)
+		//gameplay reads the actions during Update, so they are refreshed right after bevy reads the input
+		.add_systems(PreUpdate, (read_scroll, detect_device, update_action_state, update_aim).chain().after(bevy::input::InputSystem))
+		.add_systems(Update, toggle_ui.run_if(input_just_pressed(CONTROLS_KEY)).in_set(system::GameSet::Check))
+		.add_systems(Update, toggle_pause.run_if(action_just_pressed(Action::Pause)).in_set(system::GameSet::Check))
+		.add_systems(Update, (capture_rebind, rebind_buttons, refresh_ui).chain().in_set(system::GameSet::Apply))
+		.add_systems(Last, write_controls.run_if(save::changed_since_load::<ActionMap>));
+	}
+}
+
+const CONTROLS_PATH: &str = "controls.ron";
+//Opens the rebinding panel, it is not an action so it can't be unbound by accident
+const CONTROLS_KEY : KeyCode = KeyCode::F1;
+//Stick movement smaller than this is ignored, worn sticks rarely rest at zero
+const STICK_DEADZONE: f32 = 0.2;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+	MoveUp,
+	MoveDown,
+	MoveLeft,
+	MoveRight,
+	Attack,
+	Secondary,
+	Dash,
+	Interact,
+	Inventory,
+	Pause,
+	Weapon1,
+	Weapon2,
+	Weapon3,
+	Weapon4,
+	NextWeapon,
+	PreviousWeapon,
+	//on the inventory slot under the cursor
+	UseItem,
+	DropItem,
+}
+
+pub const ACTIONS: [Action; 18] = [
+	Action::MoveUp,
+	Action::MoveDown,
+	Action::MoveLeft,
+	Action::MoveRight,
+	Action::Attack,
+	Action::Secondary,
+	Action::Dash,
+	Action::Interact,
+	Action::Inventory,
+	Action::Pause,
+	Action::Weapon1,
+	Action::Weapon2,
+	Action::Weapon3,
+	Action::Weapon4,
+	Action::NextWeapon,
+	Action::PreviousWeapon,
+	Action::UseItem,
+	Action::DropItem,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Binding {
+	Key(KeyCode),
+	Mouse(MouseButton),
+	Pad(GamepadButtonType),
+	//one notch of the mouse wheel, pressed and released within the same frame
+	Scroll(ScrollDirection),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ScrollDirection {
+	Up,
+	Down,
+}
+
+impl ScrollDirection {
+	fn of(scroll: f32) -> Option<Self> {
+		if scroll > 0.0 {
+			Some(ScrollDirection::Up)
+		} else if scroll < 0.0 {
+			Some(ScrollDirection::Down)
+		} else {
+			None
+		}
+	}
+}
+
+impl Binding {
+	fn device(&self) -> Device {
+		match self {
+			Binding::Key(_) | Binding::Mouse(_) | Binding::Scroll(_) => Device::KeyboardMouse,
+			Binding::Pad(_) => Device::Gamepad,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Device {
+	#[default]
+	KeyboardMouse,
+	Gamepad,
+}
+
+//What every action is bound to, saved so rebinds outlive a single run
+#[derive(Clone, Resource, Serialize, Deserialize)]
+pub struct ActionMap {
+	pub bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+	fn default() -> Self {
+		let bindings = [
+			(Action::MoveUp,    vec![Binding::Key(KeyCode::W), Binding::Pad(GamepadButtonType::DPadUp)]),
+			(Action::MoveDown,  vec![Binding::Key(KeyCode::S), Binding::Pad(GamepadButtonType::DPadDown)]),
+			(Action::MoveLeft,  vec![Binding::Key(KeyCode::A), Binding::Pad(GamepadButtonType::DPadLeft)]),
+			(Action::MoveRight, vec![Binding::Key(KeyCode::D), Binding::Pad(GamepadButtonType::DPadRight)]),
+			(Action::Attack,    vec![Binding::Mouse(MouseButton::Left), Binding::Pad(GamepadButtonType::RightTrigger2)]),
+			(Action::Secondary, vec![Binding::Mouse(MouseButton::Right), Binding::Pad(GamepadButtonType::RightTrigger)]),
+			(Action::Dash,      vec![Binding::Key(KeyCode::F), Binding::Pad(GamepadButtonType::LeftTrigger2)]),
+			(Action::Interact,  vec![Binding::Key(KeyCode::E), Binding::Pad(GamepadButtonType::South)]),
+			(Action::Inventory, vec![Binding::Key(KeyCode::I), Binding::Pad(GamepadButtonType::North)]),
+			(Action::Pause,     vec![Binding::Key(KeyCode::P), Binding::Pad(GamepadButtonType::Start)]),
+			(Action::Weapon1,   vec![Binding::Key(KeyCode::Key1)]),
+			(Action::Weapon2,   vec![Binding::Key(KeyCode::Key2)]),
+			(Action::Weapon3,   vec![Binding::Key(KeyCode::Key3)]),
+			(Action::Weapon4,   vec![Binding::Key(KeyCode::Key4)]),
+			(Action::NextWeapon, vec![Binding::Scroll(ScrollDirection::Up), Binding::Pad(GamepadButtonType::LeftTrigger)]),
+			(Action::PreviousWeapon, vec![Binding::Scroll(ScrollDirection::Down)]),
+			(Action::UseItem,   vec![Binding::Mouse(MouseButton::Left), Binding::Pad(GamepadButtonType::West)]),
+			(Action::DropItem,  vec![Binding::Mouse(MouseButton::Right), Binding::Pad(GamepadButtonType::East)]),
+		];
+		Self { bindings: bindings.into_iter().collect() }
+	}
+}
+
+impl ActionMap {
+	//Only replaces the binding for the same device, so rebinding a key keeps the gamepad button
+	pub fn rebind(&mut self, action: Action, binding: Binding) {
+		let bindings = self.bindings.entry(action).or_default();
+		bindings.retain(|bound| bound.device() != binding.device());
+		bindings.push(binding);
+	}
+
+	fn bindings(&self, action: Action) -> &[Binding] {
+		self.bindings.get(&action).map_or(&[], |bindings| bindings.as_slice())
+	}
+}
+
+//State of every action this frame, gameplay systems read this instead of the raw input
+#[derive(Clone, Default, Resource)]
+pub struct ActionState {
+	pressed      : Vec<Action>,
+	just_pressed : Vec<Action>,
+	just_released: Vec<Action>,
+	//each axis goes from -1 to 1
+	pub move_axis: Vec2,
+	//world position being aimed at with the mouse, if there is one
+	pub aim_point: Option<Vec2>,
+	//direction being aimed at with the right stick, if it is pushed
+	pub aim_direction: Option<Vec2>,
+	//last device used, it decides where the aim comes from
+	pub device: Device,
+}
+
+impl ActionState {
+	pub fn pressed(&self, action: Action) -> bool {
+		self.pressed.contains(&action)
+	}
+
+	pub fn just_pressed(&self, action: Action) -> bool {
+		self.just_pressed.contains(&action)
+	}
+
+	pub fn just_released(&self, action: Action) -> bool {
+		self.just_released.contains(&action)
+	}
+}
+
+//Action waiting for a new binding, the next key or button pressed is used
+#[derive(Clone, Default, Resource)]
+struct Rebinding {
+	action: Option<Action>,
+	open  : bool,
+}
+
+//How far the mouse wheel turned this frame, bevy only has it as events
+#[derive(Clone, Default, Resource)]
+struct WheelScroll {
+	y: f32,
+}
+
+#[derive(Clone, Default, Component)]
+struct ControlsRoot;
+
+#[derive(Clone, Component)]
+struct RebindButton(Action);
+
+#[derive(Clone, Default, Component)]
+struct PausedText;
+
+//Bevy's input resources for every device, gamepad events sent by hand show up here just like a real pad
+#[derive(SystemParam)]
+struct InputDevices<'w> {
+	keys       : Res<'w, Input<KeyCode>>,
+	mouse      : Res<'w, Input<MouseButton>>,
+	pad_buttons: Res<'w, Input<GamepadButton>>,
+	pad_axes   : Res<'w, Axis<GamepadAxis>>,
+	gamepads   : Res<'w, Gamepads>,
+	scroll     : Res<'w, WheelScroll>,
+}
+
+impl InputDevices<'_> {
+	fn gamepad(&self) -> Option<Gamepad> {
+		self.gamepads.iter().next()
+	}
+
+	//pressed, just pressed and just released
+	fn binding_state(&self, binding: Binding) -> [bool; 3] {
+		match binding {
+			Binding::Key(code) => button_state(&self.keys, code),
+			Binding::Mouse(code) => button_state(&self.mouse, code),
+			Binding::Pad(kind) => self.gamepad()
+				.map_or([false; 3], |gamepad| button_state(&self.pad_buttons, GamepadButton::new(gamepad, kind))),
+			Binding::Scroll(direction) => {
+				let scrolled = ScrollDirection::of(self.scroll.y) == Some(direction);
+				[scrolled, scrolled, false]
+			},
+		}
+	}
+
+	fn stick(&self, x: GamepadAxisType, y: GamepadAxisType) -> Vec2 {
+		let Some(gamepad) = self.gamepad() else { return Vec2::ZERO };
+		let value = |axis| self.pad_axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.0);
+		let stick = Vec2::new(value(x), value(y));
+		if stick.length() < STICK_DEADZONE { Vec2::ZERO } else { stick.clamp_length_max(1.0) }
+	}
+
+	fn left_stick(&self) -> Vec2 {
+		self.stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY)
+	}
+
+	fn right_stick(&self) -> Vec2 {
+		self.stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY)
+	}
+}
+
+fn button_state<T: Copy + Eq + Hash + Send + Sync>(input: &Input<T>, button: T) -> [bool; 3] {
+	[input.pressed(button), input.just_pressed(button), input.just_released(button)]
+}
+
+//Same as bevy's input conditions, but for actions
+pub fn action_just_pressed(action: Action) -> impl Fn(Res<ActionState>) -> bool + Clone {
+	move |r_actions: Res<ActionState>| r_actions.just_pressed(action)
+}
+
+pub fn action_just_released(action: Action) -> impl Fn(Res<ActionState>) -> bool + Clone {
+	move |r_actions: Res<ActionState>| r_actions.just_released(action)
+}
+
+//A missing or broken file goes back to the default bindings, actions missing from it keep theirs
+fn load_controls(mut commands: Commands) {
+	let mut map = ActionMap::default();
+	let loaded = std::fs::read_to_string(CONTROLS_PATH)
+		.ok()
+		.and_then(|contents| ron::from_str::<ActionMap>(&contents).ok());
+	if let Some(loaded) = loaded {
+		map.bindings.extend(loaded.bindings);
+	}
+	commands.insert_resource(map);
+}
+
+fn write_controls(r_map: Res<ActionMap>) {
+	save::write_ron(CONTROLS_PATH, &*r_map);
+}
+
+fn read_scroll(
+	mut er_wheel: EventReader<MouseWheel>,
+	mut rm_scroll: ResMut<WheelScroll>,
+) {
+	rm_scroll.y = er_wheel.read().map(|event| event.y).sum();
+}
+
+//Any key, click, scroll or cursor movement switches to keyboard and mouse, any button or stick switches to the gamepad
+fn detect_device(
+	r_devices: InputDevices,
+	mut er_cursor: EventReader<CursorMoved>,
+	mut rm_state: ResMut<ActionState>,
+) {
+	let gamepad_used = r_devices.gamepad().is_some_and(|gamepad| {
+		r_devices.pad_buttons.get_just_pressed().any(|button| button.gamepad == gamepad)
+	}) || r_devices.left_stick() != Vec2::ZERO || r_devices.right_stick() != Vec2::ZERO;
+	let keyboard_mouse_used = r_devices.keys.get_just_pressed().next().is_some()
+		|| r_devices.mouse.get_just_pressed().next().is_some()
+		|| r_devices.scroll.y != 0.0
+		|| er_cursor.read().count() > 0;
+
+	let device = if keyboard_mouse_used {
+		Device::KeyboardMouse
+	} else if gamepad_used {
+		Device::Gamepad
+	} else {
+		return;
+	};
+	if rm_state.device != device {
+		rm_state.device = device;
+	}
+}
+
+//While the controls panel is open no action is reported, so gameplay ignores the input
+//While paused only unpausing is
+fn update_action_state(
+	r_map: Res<ActionMap>,
+	r_time: Res<Time<Virtual>>,
+	r_devices: InputDevices,
+	r_rebinding: Res<Rebinding>,
+	mut rm_state: ResMut<ActionState>,
+) {
+	let state = rm_state.as_mut();
+	state.pressed.clear();
+	state.just_pressed.clear();
+	state.just_released.clear();
+	state.move_axis = Vec2::ZERO;
+	if r_rebinding.open {
+		return;
+	}
+
+	for action in ACTIONS {
+		if r_time.is_paused() && action != Action::Pause {
+			continue;
+		}
+		let [pressed, just_pressed, just_released] = r_map.bindings(action).iter()
+			.map(|binding| r_devices.binding_state(*binding))
+			.fold([false; 3], |a, b| [a[0] || b[0], a[1] || b[1], a[2] || b[2]]);
+		if pressed {
+			state.pressed.push(action);
+		}
+		if just_pressed {
+			state.just_pressed.push(action);
+		}
+		if just_released {
+			state.just_released.push(action);
+		}
+	}
+	if r_time.is_paused() {
+		return;
+	}
+
+	//the stick takes over when pushed, otherwise the bound buttons move one step per axis
+	let axis = |negative: Action, positive: Action| {
+		state.pressed(positive) as i32 as f32 - state.pressed(negative) as i32 as f32
+	};
+	let stick = r_devices.left_stick();
+	state.move_axis = if stick != Vec2::ZERO {
+		stick
+	} else {
+		Vec2::new(axis(Action::MoveLeft, Action::MoveRight), axis(Action::MoveDown, Action::MoveUp))
+	};
+}
+
+//The mouse aims at a point in the world, the right stick aims in a direction
+fn update_aim(
+	r_time: Res<Time<Virtual>>,
+	r_rebinding: Res<Rebinding>,
+	r_devices: InputDevices,
+	mut rm_state: ResMut<ActionState>,
+	q_window: Query<&Window, With<bevy::window::PrimaryWindow>>,
+	q_camera: Query<(&Camera, &GlobalTransform)>,
+) {
+	rm_state.aim_point = None;
+	rm_state.aim_direction = None;
+	if r_rebinding.open || r_time.is_paused() {
+		return;
+	}
+
+	match rm_state.device {
+		Device::KeyboardMouse => {
+			let (Ok(window), Ok((camera, gt_camera))) = (q_window.get_single(), q_camera.get_single()) else { return };
+			rm_state.aim_point = window.cursor_position()
+				.and_then(|cursor| camera.viewport_to_world_2d(gt_camera, cursor));
+		},
+		Device::Gamepad => {
+			let stick = r_devices.right_stick();
+			if stick != Vec2::ZERO {
+				rm_state.aim_direction = Some(stick.normalize());
+			}
+		},
+	}
+}
+
+fn setup_ui(mut commands: Commands) {
+	commands.spawn((
+		TextBundle::from_section(
+			"Paused",
+			TextStyle {
+				font_size: 60.0,
+				color: Color::WHITE,
+				..default()
+			},
+		)
+		.with_style(Style {
+			position_type: PositionType::Absolute,
+			align_self: AlignSelf::Center,
+			justify_self: JustifySelf::Center,
+			display: Display::None,
+			..default()
+		}),
+		PausedText,
+	));
+
+	commands.spawn((
+		NodeBundle {
+			style: Style {
+				position_type: PositionType::Absolute,
+				width: Val::Percent(100.0),
+				height: Val::Percent(100.0),
+				flex_direction: FlexDirection::Column,
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				row_gap: Val::Px(4.0),
+				display: Display::None,
+				..default()
+			},
+			background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+			..default()
+		},
+		ControlsRoot,
+	))
+	.with_children(|parent| {
+		for action in ACTIONS {
+			parent.spawn((
+				ButtonBundle {
+					style: Style {
+						width: Val::Px(360.0),
+						padding: UiRect::all(Val::Px(4.0)),
+						..default()
+					},
+					background_color: Color::rgba(1.0, 1.0, 1.0, 0.1).into(),
+					..default()
+				},
+				RebindButton(action),
+			))
+			.with_children(|parent| {
+				parent.spawn(TextBundle::from_section(
+					"",
+					TextStyle {
+						font_size: 24.0,
+						color: Color::WHITE,
+						..default()
+					},
+				));
+			});
+		}
+	});
+}
+
+fn toggle_ui(
+	mut rm_rebinding: ResMut<Rebinding>,
+	mut q_root: Query<&mut Style, With<ControlsRoot>>,
+) {
+	rm_rebinding.open = !rm_rebinding.open;
+	rm_rebinding.action = None;
+	for mut style in q_root.iter_mut() {
+		style.display = if rm_rebinding.open { Display::Flex } else { Display::None };
+	}
+}
+
+//Pausing stops the virtual clock, so everything driven by time stops with it
+fn toggle_pause(
+	mut rm_time: ResMut<Time<Virtual>>,
+	mut q_text: Query<&mut Style, With<PausedText>>,
+) {
+	if rm_time.is_paused() {
+		rm_time.unpause();
+	} else {
+		rm_time.pause();
+	}
+	for mut style in q_text.iter_mut() {
+		style.display = if rm_time.is_paused() { Display::Flex } else { Display::None };
+	}
+}
+
+//Runs before the buttons so the click that starts a rebind is not taken as the new binding
+fn capture_rebind(
+	r_devices: InputDevices,
+	mut rm_rebinding: ResMut<Rebinding>,
+	mut rm_map: ResMut<ActionMap>,
+) {
+	let Some(action) = rm_rebinding.action else { return };
+
+	let binding = r_devices.keys.get_just_pressed()
+		.find(|key| **key != CONTROLS_KEY)
+		.map(|key| Binding::Key(*key))
+		.or_else(|| r_devices.mouse.get_just_pressed().next().map(|button| Binding::Mouse(*button)))
+		.or_else(|| r_devices.pad_buttons.get_just_pressed().next().map(|button| Binding::Pad(button.button_type)))
+		.or_else(|| ScrollDirection::of(r_devices.scroll.y).map(Binding::Scroll));
+	let Some(binding) = binding else { return };
+
+	rm_map.rebind(action, binding);
+	rm_rebinding.action = None;
+}
+
+fn rebind_buttons(
+	mut rm_rebinding: ResMut<Rebinding>,
+	q_button: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+) {
+	for (interaction, button) in q_button.iter() {
+		if *interaction == Interaction::Pressed {
+			rm_rebinding.action = Some(button.0);
+		}
+	}
+}
+
+fn refresh_ui(
+	r_map: Res<ActionMap>,
+	r_rebinding: Res<Rebinding>,
+	q_button: Query<(&RebindButton, &Children)>,
+	mut q_text: Query<&mut Text>,
+) {
+	if !r_map.is_changed() && !r_rebinding.is_changed() {
+		return;
+	}
+
+	for (button, children) in q_button.iter() {
+		let Some(mut text) = children.first().and_then(|child| q_text.get_mut(*child).ok()) else { continue };
+		let bound = if r_rebinding.action == Some(button.0) {
+			"press a key...".to_string()
+		} else {
+			r_map.bindings(button.0).iter()
+				.map(|binding| match binding {
+					Binding::Key(code) => format!("{code:?}"),
+					Binding::Mouse(code) => format!("Mouse {code:?}"),
+					Binding::Pad(code) => format!("Pad {code:?}"),
+					Binding::Scroll(direction) => format!("Scroll {direction:?}"),
+				})
+				.collect::<Vec<_>>()
+				.join(", ")
+		};
+		text.sections[0].value = format!("{:?}: {bound}", button.0);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use bevy::input::{ButtonState, InputPlugin, mouse::MouseButtonInput};
+	use bevy::input::gamepad::{
+		GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadInfo
+	};
+
+	const PAD: Gamepad = Gamepad { id: 0 };
+
+	//Input and actions only, with a gamepad connected
+	fn app() -> App {
+		let mut app = App::new();
+		app.add_plugins((MinimalPlugins, InputPlugin, ActionsPlugin))
+		.add_event::<CursorMoved>();
+		app.update();
+		//a controls.ron next to the manifest must not change the bindings tested here, nor be written by them
+		*app.world.resource_mut::<ActionMap>().bypass_change_detection() = ActionMap::default();
+
+		let connection = GamepadConnection::Connected(GamepadInfo { name: "test pad".to_string() });
+		app.world.send_event(GamepadConnectionEvent::new(PAD, connection));
+		app.update();
+		app
+	}
+
+	fn pad_button(app: &mut App, button: GamepadButtonType, value: f32) {
+		app.world.send_event(GamepadButtonChangedEvent::new(PAD, button, value));
+		app.update();
+	}
+
+	fn pad_axis(app: &mut App, axis: GamepadAxisType, value: f32) {
+		app.world.send_event(GamepadAxisChangedEvent::new(PAD, axis, value));
+		app.update();
+	}
+
+	fn state(app: &App) -> &ActionState {
+		app.world.resource::<ActionState>()
+	}
+
+	#[test]
+	fn left_stick_moves() {
+		let mut app = app();
+		pad_axis(&mut app, GamepadAxisType::LeftStickX, 1.0);
+		assert_eq!(state(&app).move_axis, Vec2::X);
+		assert_eq!(state(&app).device, Device::Gamepad);
+
+		pad_axis(&mut app, GamepadAxisType::LeftStickX, STICK_DEADZONE / 2.0);
+		assert_eq!(state(&app).move_axis, Vec2::ZERO);
+	}
+
+	#[test]
+	fn right_stick_aims() {
+		let mut app = app();
+		pad_axis(&mut app, GamepadAxisType::RightStickY, -0.5);
+		assert_eq!(state(&app).aim_direction, Some(Vec2::NEG_Y));
+		assert_eq!(state(&app).move_axis, Vec2::ZERO);
+	}
+
+	#[test]
+	fn triggers_attack_and_dash() {
+		let mut app = app();
+		pad_button(&mut app, GamepadButtonType::RightTrigger2, 1.0);
+		assert!(state(&app).just_pressed(Action::Attack));
+		assert!(!state(&app).pressed(Action::Dash));
+
+		pad_button(&mut app, GamepadButtonType::LeftTrigger2, 1.0);
+		assert!(state(&app).just_pressed(Action::Dash));
+		assert!(state(&app).pressed(Action::Attack));
+		assert!(!state(&app).just_pressed(Action::Attack));
+
+		pad_button(&mut app, GamepadButtonType::RightTrigger2, 0.0);
+		assert!(state(&app).just_released(Action::Attack));
+		assert!(state(&app).pressed(Action::Dash));
+	}
+
+	#[test]
+	fn mouse_takes_the_aim_back() {
+		let mut app = app();
+		pad_axis(&mut app, GamepadAxisType::RightStickX, 1.0);
+		assert_eq!(state(&app).device, Device::Gamepad);
+		assert_eq!(state(&app).aim_direction, Some(Vec2::X));
+
+		pad_axis(&mut app, GamepadAxisType::RightStickX, 0.0);
+		app.world.send_event(MouseButtonInput {
+			button: MouseButton::Left,
+			state : ButtonState::Pressed,
+			window: Entity::PLACEHOLDER,
+		});
+		app.update();
+		assert_eq!(state(&app).device, Device::KeyboardMouse);
+		assert_eq!(state(&app).aim_direction, None);
+		assert!(state(&app).just_pressed(Action::Attack));
+	}
+}
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+use std::f32::consts::TAU;
+
+use crate::projectile::{OnHit, ProjectileDef};
+
+//Bullet the boss bursts are made of
+pub const BOSS_BULLET: ProjectileDef = ProjectileDef {
+	speed: 120.0,
+	lifetime: 3.0,
+	damage: 6.0,
+	size: 6.0,
+	color: Color::ORANGE,
+	pierce: 0,
+	homing: 0.0,
+	on_hit: OnHit::Knockback(80.0),
+};
+
+//What a boss move does once its windup is over, angles are in radians and times in seconds
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AttackPattern {
+	//rushes along the direction it was aimed at when the windup ended
+	Charge { speed: f32 },
+	//volleys of projectiles spread evenly around the boss, each one turned by `turn` from the last
+	Radial { count: u32, volleys: u32, interval: f32, turn: f32, projectile: ProjectileDef },
+	//one shot at a time, going round the arms in turn and turning by `turn` after every shot
+	Spiral { arms: u32, shots: u32, interval: f32, turn: f32, projectile: ProjectileDef },
+	//hits everything around the boss at once
+	Slam { radius: f32, damage: f32, knockback: f32 },
+	//hits everything in a cone in front of the boss, angle is the full opening
+	Sweep { radius: f32, angle: f32, damage: f32, knockback: f32 },
+	//spawns enemies of an archetype in a ring around the boss
+	Summon { archetype: &'static str, count: u32, radius: f32 },
+}
+
+impl AttackPattern {
+	//How many volleys should have gone off after `elapsed` seconds of the attack, the first one is immediate
+	pub fn volleys_due(&self, elapsed: f32) -> u32 {
+		let (volleys, interval) = match *self {
+			AttackPattern::Charge { .. } => return 0,
+			AttackPattern::Slam { .. } | AttackPattern::Sweep { .. } | AttackPattern::Summon { .. } => return 1,
+			AttackPattern::Radial { volleys, interval, .. } => (volleys, interval),
+			AttackPattern::Spiral { shots, interval, .. } => (shots, interval),
+		};
+		if interval <= 0.0 {
+			return volleys;
+		}
+		((elapsed / interval) as u32 + 1).min(volleys)
+	}
+
+	//Directions the projectiles of a volley are shot at, starting from where the boss aims
+	pub fn volley_directions(&self, volley: u32, aim: Vec2) -> Vec<Vec2> {
+		match *self {
+			AttackPattern::Radial { count, turn, .. } => ring(count, Vec2::from_angle(turn * volley as f32).rotate(aim)),
+			AttackPattern::Spiral { arms, turn, .. } => {
+				let arm = TAU * (volley % arms.max(1)) as f32 / arms.max(1) as f32;
+				vec![Vec2::from_angle(arm + turn * volley as f32).rotate(aim)]
+			},
+			_ => Vec::new(),
+		}
+	}
+
+	//Whether something at `offset` from the boss is caught by a slam or a sweep aimed at `aim`
+	pub fn area_contains(&self, offset: Vec2, aim: Vec2) -> bool {
+		match *self {
+			AttackPattern::Slam { radius, .. } => offset.length() <= radius,
+			AttackPattern::Sweep { radius, angle, .. } => offset.length() <= radius && aim.angle_between(offset).abs() <= angle / 2.0,
+			_ => false,
+		}
+	}
+}
+
+//Evenly spaced directions, the first one being `start`
+pub fn ring(count: u32, start: Vec2) -> Vec<Vec2> {
+	(0..count)
+		.map(|i| Vec2::from_angle(TAU * i as f32 / count as f32).rotate(start))
+		.collect()
+}
+
+pub fn summon_positions(center: Vec2, count: u32, radius: f32) -> Vec<Vec2> {
+	ring(count, Vec2::X).into_iter().map(|direction| center + direction * radius).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+	const RADIAL: AttackPattern = AttackPattern::Radial { count: 4, volleys: 3, interval: 0.3, turn: 0.1, projectile: BOSS_BULLET };
+	const SPIRAL: AttackPattern = AttackPattern::Spiral { arms: 3, shots: 12, interval: 0.05, turn: 0.1, projectile: BOSS_BULLET };
+	const SWEEP : AttackPattern = AttackPattern::Sweep { radius: 50.0, angle: FRAC_PI_2, damage: 10.0, knockback: 100.0 };
+
+	fn assert_close(a: Vec2, b: Vec2) {
+		assert!(a.abs_diff_eq(b, 1e-5), "{a} is not {b}");
+	}
+
+	#[test]
+	fn volleys_due() {
+		assert_eq!(RADIAL.volleys_due(0.0), 1);
+		assert_eq!(RADIAL.volleys_due(0.15), 1);
+		assert_eq!(RADIAL.volleys_due(0.45), 2);
+		assert_eq!(RADIAL.volleys_due(10.0), 3);
+
+		let burst = AttackPattern::Radial { count: 8, volleys: 3, interval: 0.0, turn: 0.0, projectile: BOSS_BULLET };
+		assert_eq!(burst.volleys_due(0.0), 3);
+	}
+
+	#[test]
+	fn radial_directions() {
+		let first = RADIAL.volley_directions(0, Vec2::X);
+		assert_eq!(first.len(), 4);
+		for (i, direction) in first.iter().enumerate() {
+			assert_close(*direction, Vec2::from_angle(FRAC_PI_2 * i as f32));
+		}
+
+		let third = RADIAL.volley_directions(2, Vec2::X);
+		assert_eq!(third.len(), 4);
+		for (i, direction) in third.iter().enumerate() {
+			assert_close(*direction, Vec2::from_angle(0.2 + FRAC_PI_2 * i as f32));
+		}
+	}
+
+	#[test]
+	fn spiral_directions() {
+		//one shot per volley, going round the three arms
+		for volley in 0..SPIRAL.volleys_due(10.0) {
+			let directions = SPIRAL.volley_directions(volley, Vec2::Y);
+			assert_eq!(directions.len(), 1);
+			let arm = TAU * (volley % 3) as f32 / 3.0;
+			assert_close(directions[0], Vec2::from_angle(arm + 0.1 * volley as f32).rotate(Vec2::Y));
+		}
+		assert_close(SPIRAL.volley_directions(0, Vec2::Y)[0], Vec2::Y);
+		assert_close(SPIRAL.volley_directions(3, Vec2::Y)[0], Vec2::from_angle(0.3).rotate(Vec2::Y));
+	}
+
+	#[test]
+	fn sweep_edges() {
+		let edge = |angle: f32, length: f32| Vec2::from_angle(angle) * length;
+		assert!(SWEEP.area_contains(edge(0.0, 50.0), Vec2::X));
+		assert!(!SWEEP.area_contains(edge(0.0, 50.1), Vec2::X));
+		assert!(SWEEP.area_contains(edge(FRAC_PI_4 - 0.01, 40.0), Vec2::X));
+		assert!(SWEEP.area_contains(edge(-FRAC_PI_4 + 0.01, 40.0), Vec2::X));
+		assert!(!SWEEP.area_contains(edge(FRAC_PI_4 + 0.01, 40.0), Vec2::X));
+		assert!(!SWEEP.area_contains(edge(-FRAC_PI_4 - 0.01, 40.0), Vec2::X));
+		assert!(!SWEEP.area_contains(Vec2::NEG_X * 10.0, Vec2::X));
+	}
+
+	#[test]
+	fn summons_land_on_the_radius() {
+		let center = Vec2::new(10.0, -20.0);
+		let positions = summon_positions(center, 5, 40.0);
+		assert_eq!(positions.len(), 5);
+		for position in positions {
+			assert!((position.distance(center) - 40.0).abs() < 1e-4);
+		}
+	}
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::components;
+use crate::events;
+use crate::miscellaneous;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct BlockPlugin;
+
+impl Plugin for BlockPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<HitStop>()
+		.add_systems(Update, (hold_block, tick_stagger).in_set(system::GameSet::ContinousAction))
+		.add_systems(Update, parry_effects.in_set(system::GameSet::Apply))
+		//hit-stop slows down the virtual clock, so it is timed with the real one
+		.add_systems(Update, end_hit_stop);
+	}
+}
+
+//Seconds after a block starts in which hits are parried
+pub const PARRY_WINDOW : f32 = 0.15;
+//Hits further away from the blocking direction than this angle are not blocked
+const BLOCK_HALF_ANGLE : f32 = 1.2;
+const STAGGER_TIME     : f32 = 1.0;
+const PARRY_KNOCKBACK  : f32 = 400.0;
+//Real seconds the game is slowed down for after a parry
+const HIT_STOP_TIME    : f32 = 0.12;
+const HIT_STOP_SPEED   : f32 = 0.05;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlockOutcome {
+	Parried,
+	//damage is multiplied by the value
+	Blocked(f32),
+	Unblocked,
+}
+
+#[derive(Clone, Default, Resource)]
+struct HitStop {
+	timer: Option<Timer>,
+}
+
+//How a hit coming from the origin is taken by a target at the position
+pub fn block_outcome(blocking: Option<&components::Blocking>, position: Vec2, origin: Vec2) -> BlockOutcome {
+	let Some(blocking) = blocking else { return BlockOutcome::Unblocked };
+
+	let from = origin - position;
+	//hits from right on top of the target are taken as coming from the front
+	if from.length() > f32::EPSILON && blocking.facing.angle_between(from).abs() > BLOCK_HALF_ANGLE {
+		return BlockOutcome::Unblocked;
+	}
+	if !blocking.parry.finished() {
+		return BlockOutcome::Parried;
+	}
+	BlockOutcome::Blocked(1.0 - blocking.reduction)
+}
+
+//The block faces where the player aims
+fn hold_block(
+	mut q_blocking: Query<&mut components::Blocking>,
+	q_weapon: Query<&components::PlayerWeapon>,
+	r_time: Res<Time>,
+) {
+	let Ok(weapon) = q_weapon.get_single() else { return };
+	for mut blocking in q_blocking.iter_mut() {
+		blocking.facing = weapon.aim;
+		blocking.parry.tick(r_time.delta());
+	}
+}
+
+fn tick_stagger(
+	mut commands: Commands,
+	mut q_stagger: Query<(Entity, &mut components::Stagger)>,
+	r_time: Res<Time>,
+) {
+	for (id, mut stagger) in q_stagger.iter_mut() {
+		stagger.timer.tick(r_time.delta());
+		if stagger.timer.finished() {
+			commands.entity(id).remove::<components::Stagger>();
+		}
+	}
+}
+
+//Staggers and pushes back the attacker, then freezes the game for a moment
+fn parry_effects(
+	mut commands: Commands,
+	mut er_parry: EventReader<events::ParryEvent>,
+	q_attacker: Query<&Transform>,
+	mut rm_hit_stop: ResMut<HitStop>,
+	mut rm_time: ResMut<Time<Virtual>>,
+	mut ew_shake: EventWriter<events::ShakeEvent>,
+) {
+	for event in er_parry.read() {
+		miscellaneous::generic_particle_burst(
+			&mut commands,
+			event.position.extend(950.0),
+			300.0,
+			0.4,
+			1.5,
+			120,
+			(Color::CYAN..Color::rgba(1.0, 1.0, 1.0, 0.0)).into()
+		);
+		ew_shake.send(events::ShakeEvent { intensity: 2.0 });
+
+		rm_hit_stop.timer = Some(Timer::from_seconds(HIT_STOP_TIME, TimerMode::Once));
+		rm_time.set_relative_speed(HIT_STOP_SPEED);
+
+		let Some(attacker) = event.attacker else { continue };
+		let Ok(transform) = q_attacker.get(attacker) else { continue };
+		let away = (transform.translation.truncate() - event.position).normalize_or_zero();
+		commands.entity(attacker).insert((
+			components::Stagger { timer: Timer::from_seconds(STAGGER_TIME, TimerMode::Once) },
+			components::Knockback { v: away * PARRY_KNOCKBACK, decay: miscellaneous::KNOCKBACK_DECAY },
+		));
+	}
+}
+
+fn end_hit_stop(
+	mut rm_hit_stop: ResMut<HitStop>,
+	mut rm_time: ResMut<Time<Virtual>>,
+	r_real_time: Res<Time<Real>>,
+) {
+	let Some(timer) = rm_hit_stop.timer.as_mut() else { return };
+	timer.tick(r_real_time.delta());
+	if timer.finished() {
+		rm_hit_stop.timer = None;
+		rm_time.set_relative_speed(1.0);
+	}
+}
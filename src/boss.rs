@@ -1,74 +1,368 @@
 use bevy::prelude::*;
 
+use crate::attack_pattern::{self, AttackPattern, BOSS_BULLET};
+use crate::block;
 use crate::components;
+use crate::enemy;
+use crate::events;
+use crate::map;
+use crate::miscellaneous;
+use crate::projectile;
 use crate::system;
+use crate::telegraph::{self, TelegraphShape};
 
-const BOSS_SPEED: f32 = 60.0;
+//Looks, health and drops of the boss live in its archetype file
+const BOSS_ARCHETYPE : &str = "boss";
+//The boss waits until the player gets this close
+const AGGRO_RANGE    : f32 = 220.0;
+const ENRAGE_DURATION: f32 = 1.2;
+const BANNER_DURATION: f32 = 2.0;
+const SLAM_SHAKE     : f32 = 3.0;
+//Width of the line warning about a charge, about as wide as the boss
+const CHARGE_WIDTH   : f32 = 32.0;
+//The length of a charge is worked out in steps of about a frame
+const CHARGE_STEP    : f32 = 1.0 / 60.0;
 
 #[derive(Clone, Default)]
 pub struct BossPlugin;
 
 impl Plugin for BossPlugin {
 	fn build(&self, app: &mut App) {
-		app
-		.add_systems(Update, boss_movement.in_set(system::GameSet::ContinousAction))
-		.add_systems(Startup, setup);
+		app.add_systems(Startup, check_boss_archetype)
+		.add_systems(Update, boss_phases.in_set(system::GameSet::SingleAction))
+		.add_systems(Update, (boss_attacks, boss_brain).chain().in_set(system::GameSet::ContinousAction))
+		.add_systems(Update, spawn_boss.in_set(system::GameSet::CommandsAction))
+		.add_systems(Update, phase_banner.in_set(system::GameSet::Apply));
 	}
 }
 
-fn boss_movement(
-	mut q_velocity: Query<(&mut components::Velocity, &Transform, &components::Boss)>,
-	q_player_transform: Query<&Transform, (With<components::PlayerCharacter>, Without<components::Boss>)>
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum BossState {
+	//waiting for the player to come close
+	#[default]
+	Idle,
+	Chase,
+	//standing still, giving the player time to react
+	Windup,
+	Attack,
+	Recover,
+	//roaring after a phase change, nothing else happens until it ends
+	Enraged,
+}
+
+//One attack of a move set, timings are in seconds
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BossMove {
+	//the boss chases until the player is this close
+	pub range   : f32,
+	pub windup  : f32,
+	//volleys that are not due once this runs out are never shot
+	pub active  : f32,
+	pub recovery: f32,
+	pub pattern : AttackPattern,
+}
+
+impl BossMove {
+	//Warning shown during the windup, bursts and summons come from the boss itself so they have none
+	pub fn telegraph(&self, metadata: &map::TileMetadata, from: Vec2, direction: Vec2) -> Option<TelegraphShape> {
+		match self.pattern {
+			AttackPattern::Charge { speed } => Some(TelegraphShape::Line {
+				length: charge_length(metadata, from, direction, speed, self.active),
+				width : CHARGE_WIDTH,
+			}),
+			AttackPattern::Slam { radius, .. } => Some(TelegraphShape::Circle { radius }),
+			AttackPattern::Sweep { radius, angle, .. } => Some(TelegraphShape::Cone { radius, angle }),
+			_ => None,
+		}
+	}
+}
+
+pub struct BossPhase {
+	//the phase starts once health falls to this fraction of the max
+	pub threshold: f32,
+	pub speed    : f32,
+	pub color    : Color,
+	pub moves    : &'static [BossMove],
+}
+
+pub const PHASES: [BossPhase; 3] = [
+	BossPhase {
+		threshold: 1.0,
+		speed: 1.0,
+		color: Color::RED,
+		moves: &[
+			BossMove { range: 70.0, windup: 0.6, active: 0.25, recovery: 0.8, pattern: AttackPattern::Charge { speed: 250.0 } },
+			BossMove {
+				range: 180.0, windup: 0.8, active: 0.1, recovery: 1.0,
+				pattern: AttackPattern::Radial { count: 8, volleys: 1, interval: 0.0, turn: 0.0, projectile: BOSS_BULLET },
+			},
+		],
+	},
+	BossPhase {
+		threshold: 0.6,
+		speed: 1.3,
+		color: Color::ORANGE_RED,
+		moves: &[
+			BossMove { range: 140.0, windup: 0.7, active: 0.4, recovery: 0.8, pattern: AttackPattern::Charge { speed: 350.0 } },
+			BossMove { range: 60.0, windup: 0.7, active: 0.1, recovery: 0.9, pattern: AttackPattern::Slam { radius: 60.0, damage: 15.0, knockback: 250.0 } },
+			BossMove {
+				range: 70.0, windup: 0.5, active: 0.1, recovery: 0.6,
+				pattern: AttackPattern::Sweep { radius: 80.0, angle: 1.6, damage: 10.0, knockback: 200.0 },
+			},
+			BossMove {
+				range: 180.0, windup: 0.6, active: 0.7, recovery: 0.8,
+				pattern: AttackPattern::Radial { count: 12, volleys: 3, interval: 0.3, turn: 0.13, projectile: BOSS_BULLET },
+			},
+		],
+	},
+	BossPhase {
+		threshold: 0.3,
+		speed: 1.6,
+		color: Color::PURPLE,
+		moves: &[
+			BossMove {
+				range: 200.0, windup: 0.5, active: 1.25, recovery: 0.6,
+				pattern: AttackPattern::Spiral { arms: 3, shots: 36, interval: 0.033, turn: 0.08, projectile: BOSS_BULLET },
+			},
+			BossMove { range: 70.0, windup: 0.3, active: 0.2, recovery: 0.3, pattern: AttackPattern::Charge { speed: 300.0 } },
+			BossMove {
+				range: 70.0, windup: 0.35, active: 0.1, recovery: 0.4,
+				pattern: AttackPattern::Sweep { radius: 90.0, angle: 2.2, damage: 10.0, knockback: 250.0 },
+			},
+			BossMove { range: 200.0, windup: 0.8, active: 0.1, recovery: 0.6, pattern: AttackPattern::Summon { archetype: "slime", count: 3, radius: 40.0 } },
+			BossMove { range: 60.0, windup: 0.5, active: 0.1, recovery: 0.5, pattern: AttackPattern::Slam { radius: 70.0, damage: 15.0, knockback: 300.0 } },
+		],
+	},
+];
+
+//Phases only move forward, healing does not bring an earlier one back
+pub fn phase_for(health: &components::Health) -> usize {
+	let fraction = health.current / health.max;
+	PHASES.iter().rposition(|phase| fraction <= phase.threshold).unwrap_or(0)
+}
+
+//A phase change cuts the windup short, so its warning goes away with it
+fn boss_phases(
+	mut commands: Commands,
+	mut q_boss: Query<(Entity, &components::Health, &mut components::BossBrain)>,
+	q_telegraph: Query<(Entity, &components::Telegraph)>,
+	mut ew_phase: EventWriter<events::BossPhaseEvent>,
 ) {
-	let Ok((mut velocity, transform, values)) = q_velocity.get_single_mut() else { return };
-	let Ok(target_transform) = q_player_transform.get_single() else { return };
+	for (id, health, mut brain) in q_boss.iter_mut() {
+		let phase = phase_for(health);
+		if phase <= brain.phase {
+			continue;
+		}
+		brain.phase = phase;
+		brain.next_move = 0;
+		brain.current = None;
+		brain.enter(BossState::Enraged, ENRAGE_DURATION);
+		ew_phase.send(events::BossPhaseEvent { id, phase });
 
-	let pos = transform.translation.truncate();
-	let target_pos = target_transform.translation.truncate();
+		for (telegraph_id, telegraph) in q_telegraph.iter() {
+			if telegraph.owner == Some(id) {
+				commands.entity(telegraph_id).despawn_recursive();
+			}
+		}
+	}
+}
 
-	let direction = -(pos - target_pos).normalize();
-	velocity.v += direction * values.speed;
+//Surfaces slow a charge down like anything else walking on them, and walls stop it
+fn charge_length(metadata: &map::TileMetadata, from: Vec2, direction: Vec2, speed: f32, seconds: f32) -> f32 {
+	let steps = (seconds / CHARGE_STEP).ceil().max(1.0) as usize;
+	let dt = seconds / steps as f32;
+	let mut length = 0.0;
+	for _ in 0..steps {
+		let position = from + direction * length;
+		let step = speed * dt * miscellaneous::surface_speed(metadata, position);
+		if !metadata.is_walkable(position + direction * step) {
+			break;
+		}
+		length += step;
+	}
+	length
 }
 
-fn setup(
+//Staggered bosses are frozen in whatever they were doing
+//Moves are aimed when their windup starts, so the warning shows exactly where they land
+fn boss_brain(
 	mut commands: Commands,
-	r_asset_server: Res<AssetServer>,
-    mut rm_texture_atlases: ResMut<Assets<TextureAtlas>>
+	mut q_boss: Query<(Entity, &mut components::Velocity, &Transform, &components::Boss, &mut components::BossBrain), Without<components::Stagger>>,
+	q_player_transform: Query<&Transform, (With<components::PlayerCharacter>, Without<components::Boss>)>,
+	q_path: Query<&components::PathFollower>,
+	r_tile_metadata: Res<map::TileMetadata>,
+	r_time: Res<Time>,
 ) {
-    let enemy_texture = r_asset_server.load("textures/rpg/chars/hat-guy/hat-guy.png");
-	let size = Vec2::new(16.0, 22.0);
-    let enemy_texture_atlas =
-        TextureAtlas::from_grid(enemy_texture, size, 1, 1, None, None);
-    let enemy_texture_atlas = rm_texture_atlases.add(enemy_texture_atlas);
-
-	let size = size * 2.0;
-	let enemy_id = commands.spawn((
-		SpatialBundle {
-			transform: Transform::from_xyz(0.0, 200.0, 900.0),
-			..default()
+	let Ok((id, mut velocity, transform, values, mut brain)) = q_boss.get_single_mut() else { return };
+	let Ok(target_transform) = q_player_transform.get_single() else { return };
+
+	let to_player = target_transform.translation.truncate() - transform.translation.truncate();
+	let direction = to_player.normalize_or_zero();
+	let phase = &PHASES[brain.phase];
+	brain.timer.tick(r_time.delta());
+	let finished = brain.timer.finished();
+
+	match brain.state {
+		BossState::Idle => {
+			if to_player.length() < AGGRO_RANGE {
+				brain.enter(BossState::Chase, 0.0);
+			}
 		},
-		components::Velocity::default(),
-		components::Health { current: 100.0, ..default() },
-		components::Intersect { size },
-		components::DamageZone { damage: 2.0, ignore: None },
-		components::Boss {
-			speed: BOSS_SPEED,
-			..default()
+		BossState::Chase => {
+			//walks around whatever is in the way, but attacks are still aimed straight at the player
+			let heading = q_path.get(id).ok().and_then(|path| path.heading(transform.translation.truncate())).unwrap_or(direction);
+			velocity.v += heading * values.speed * phase.speed;
+			let next = phase.moves[brain.next_move % phase.moves.len()];
+			if to_player.length() <= next.range {
+				brain.next_move += 1;
+				brain.current = Some(next);
+				brain.fired = 0;
+				brain.direction = direction;
+				brain.enter(BossState::Windup, next.windup);
+				let position = transform.translation.truncate();
+				if let Some(shape) = next.telegraph(&r_tile_metadata, position, direction) {
+					telegraph::spawn_telegraph(&mut commands, shape, position, direction, next.windup, Color::RED, Some(id));
+				}
+			}
+		},
+		BossState::Windup => {
+			if finished {
+				let active = brain.current.map_or(0.0, |current| current.active);
+				brain.enter(BossState::Attack, active);
+			}
+		},
+		BossState::Attack => {
+			if let Some(AttackPattern::Charge { speed }) = brain.current.map(|current| current.pattern) {
+				velocity.v += brain.direction * speed;
+			}
+			if finished {
+				let recovery = brain.current.map_or(0.0, |current| current.recovery);
+				brain.enter(BossState::Recover, recovery);
+			}
 		},
-		components::WalkAnimate::new(20.0, 1.0, 2.0)
-	)).with_children(|parent| {
-		parent.spawn(
-			SpriteSheetBundle {
-				sprite: TextureAtlasSprite {
-					custom_size: Some(size),
-					index: 0,
-					..default()
-				},
-            	texture_atlas: enemy_texture_atlas,
-            	..default()
+		BossState::Recover | BossState::Enraged => {
+			if finished {
+				brain.current = None;
+				brain.enter(BossState::Chase, 0.0);
+			}
+		},
+	}
+}
+
+//What a slam or sweep needs to know about the player it lands on
+type SlamTarget<'a> = (Entity, &'a mut components::Health, &'a Transform, Option<&'a components::Blocking>, Has<components::Invulnerable>);
+
+//Shoots, slams and summons for the current move, runs before the brain so the last volley is not cut off
+//Slams and sweeps skip an invulnerable player entirely, knockback included
+fn boss_attacks(
+	mut commands: Commands,
+	mut q_boss: Query<(Entity, &Transform, &mut components::BossBrain), Without<components::Stagger>>,
+	mut q_player: Query<SlamTarget, With<components::PlayerCharacter>>,
+	r_archetypes: Res<enemy::EnemyArchetypes>,
+	mut ew_shake: EventWriter<events::ShakeEvent>,
+	mut ew_parry: EventWriter<events::ParryEvent>,
+) {
+	let Ok((id, transform, mut brain)) = q_boss.get_single_mut() else { return };
+	if brain.state != BossState::Attack {
+		return;
+	}
+	let Some(current) = brain.current else { return };
+
+	let pattern = current.pattern;
+	let due = pattern.volleys_due(brain.timer.elapsed_secs());
+	while brain.fired < due {
+		let volley = brain.fired;
+		brain.fired += 1;
+		match pattern {
+			AttackPattern::Charge { .. } => (),
+			AttackPattern::Radial { projectile, .. } | AttackPattern::Spiral { projectile, .. } => {
+				for direction in pattern.volley_directions(volley, brain.direction) {
+					projectile::spawn_projectile(&mut commands, projectile, transform.translation, direction, true, Some(id));
+				}
+			},
+			AttackPattern::Slam { radius, damage, knockback } | AttackPattern::Sweep { radius, damage, knockback, .. } => {
+				let center = match pattern {
+					AttackPattern::Sweep { .. } => transform.translation + (brain.direction * radius / 2.0).extend(0.0),
+					_ => transform.translation,
+				};
+				miscellaneous::generic_particle_burst(
+					&mut commands,
+					center,
+					radius * 3.0,
+					0.3,
+					2.0,
+					60,
+					(Color::rgb(0.5, 0.4, 0.3)..Color::rgba(0.5, 0.4, 0.3, 0.0)).into()
+				);
+				ew_shake.send(events::ShakeEvent { intensity: SLAM_SHAKE });
+
+				let Ok((player_id, mut health, player_transform, blocking, invulnerable)) = q_player.get_single_mut() else { continue };
+				if invulnerable {
+					continue;
+				}
+				let position = player_transform.translation.truncate();
+				let offset = position - transform.translation.truncate();
+				if !pattern.area_contains(offset, brain.direction) {
+					continue;
+				}
+				let multiplier = match block::block_outcome(blocking, position, transform.translation.truncate()) {
+					block::BlockOutcome::Parried => {
+						ew_parry.send(events::ParryEvent { attacker: Some(id), position });
+						continue;
+					},
+					block::BlockOutcome::Blocked(multiplier) => multiplier,
+					block::BlockOutcome::Unblocked => 1.0,
+				};
+				health.unapplied_damage += damage * multiplier;
+				commands.entity(player_id).insert(components::Knockback {
+					v: offset.normalize_or_zero() * knockback,
+					decay: miscellaneous::KNOCKBACK_DECAY,
+				});
 			},
-		);
-	}).id();
+			AttackPattern::Summon { archetype, count, radius } => {
+				for position in attack_pattern::summon_positions(transform.translation.truncate(), count, radius) {
+					enemy::spawn_enemy(&mut commands, &r_archetypes, archetype, position);
+				}
+			},
+		}
+	}
+}
+
+//Announces the new phase and recolors the boss health meter
+fn phase_banner(
+	mut commands: Commands,
+	mut er_phase: EventReader<events::BossPhaseEvent>,
+	mut q_meter: Query<(&components::HealthMeter, &mut BackgroundColor)>,
+) {
+	for event in er_phase.read() {
+		for (meter, mut color) in q_meter.iter_mut() {
+			if meter.id == event.id {
+				*color = PHASES[event.phase].color.into();
+			}
+		}
+
+		miscellaneous::spawn_banner(&mut commands, format!("Phase {}", event.phase + 1), PHASES[event.phase].color, BANNER_DURATION);
+	}
+}
+
+//The boss only shows up once the last wave is cleared
+//Without its archetype the run ends at the last wave, told right away rather than after all of them
+fn check_boss_archetype(r_archetypes: Res<enemy::EnemyArchetypes>) {
+	if r_archetypes.get(BOSS_ARCHETYPE).is_none() {
+		warn!("the {BOSS_ARCHETYPE} enemy archetype is missing or could not be loaded, no boss will show up");
+	}
+}
+
+fn spawn_boss(
+	mut commands: Commands,
+	mut er_wave_cleared: EventReader<events::WaveClearedEvent>,
+	r_archetypes: Res<enemy::EnemyArchetypes>,
+) {
+	if !er_wave_cleared.read().any(|event| event.last) {
+		return;
+	}
+
+	let Some(enemy_id) = enemy::spawn_enemy(&mut commands, &r_archetypes, BOSS_ARCHETYPE, Vec2::new(0.0, 200.0)) else { return };
 
 	commands.spawn((
 		NodeBundle {
@@ -84,4 +378,5 @@ fn setup(
 		},
 		components::HealthMeter { id: enemy_id },
 	));
+	miscellaneous::spawn_banner(&mut commands, "The boss is here".to_string(), Color::RED, BANNER_DURATION);
 }
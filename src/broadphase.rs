@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::components;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct BroadphasePlugin;
+
+impl Plugin for BroadphasePlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<SpatialHash>()
+		.add_systems(Update, rebuild_spatial_hash.in_set(system::GameSet::Check));
+	}
+}
+
+//Size of a cell of the hash in world units, about as big as the bigger colliders
+const CELL_SIZE: f32 = 32.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SpatialEntry {
+	pub id      : Entity,
+	pub position: Vec2,
+	pub size    : Vec2,
+}
+
+//Every collider sorted into a grid, so only things in nearby cells have to be compared
+#[derive(Clone, Default, Resource)]
+pub struct SpatialHash {
+	entries: Vec<SpatialEntry>,
+	cells  : HashMap<IVec2, Vec<usize>>,
+}
+
+impl SpatialHash {
+	fn cell(position: Vec2) -> IVec2 {
+		(position / CELL_SIZE).floor().as_ivec2()
+	}
+
+	fn cells_overlapped(position: Vec2, size: Vec2) -> impl Iterator<Item = IVec2> {
+		let min = Self::cell(position - size / 2.0);
+		let max = Self::cell(position + size / 2.0);
+		(min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+	}
+
+	pub fn clear(&mut self) {
+		self.entries.clear();
+		self.cells.clear();
+	}
+
+	pub fn insert(&mut self, id: Entity, position: Vec2, size: Vec2) {
+		let index = self.entries.len();
+		self.entries.push(SpatialEntry { id, position, size });
+		for cell in Self::cells_overlapped(position, size) {
+			self.cells.entry(cell).or_default().push(index);
+		}
+	}
+
+	pub fn entries(&self) -> &[SpatialEntry] {
+		&self.entries
+	}
+
+	//Everything sharing a cell with the area, each entry once. They are only candidates, they may not overlap it
+	pub fn query(&self, position: Vec2, size: Vec2) -> Vec<SpatialEntry> {
+		let mut indices: Vec<usize> = Self::cells_overlapped(position, size)
+			.filter_map(|cell| self.cells.get(&cell))
+			.flatten()
+			.copied()
+			.collect();
+		indices.sort_unstable();
+		indices.dedup();
+		indices.into_iter().map(|index| self.entries[index]).collect()
+	}
+
+	//Everything whose center is within `radius` of the position
+	pub fn neighbours(&self, position: Vec2, radius: f32) -> impl Iterator<Item = SpatialEntry> {
+		self.query(position, Vec2::splat(radius * 2.0))
+			.into_iter()
+			.filter(move |entry| entry.position.distance(position) <= radius)
+	}
+}
+
+pub fn rebuild_spatial_hash(
+	mut rm_spatial_hash: ResMut<SpatialHash>,
+	q_intersect: Query<(Entity, &components::Intersect, &Transform)>,
+) {
+	rm_spatial_hash.clear();
+	for (id, intersect, transform) in q_intersect.iter() {
+		rm_spatial_hash.insert(id, transform.translation.truncate(), intersect.size);
+	}
+}
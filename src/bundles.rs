@@ -2,6 +2,7 @@ use bevy::prelude::*;
 use rand::prelude::*;
 
 use crate::components;
+use crate::inventory;
 
 #[derive(Bundle, Default, Clone)]
 pub struct GhostedSpriteBundle {
@@ -20,6 +21,12 @@ pub struct PlayerBundle {
 	pub player_char         : components::PlayerCharacter,
 	pub velocity            : components::Velocity,
 	pub health              : components::Health,
+	pub depth_sort          : components::DepthSort,
+	pub wallet              : components::Wallet,
+	pub inventory           : inventory::Inventory,
+	pub focus               : components::Focus,
+	pub dash                : components::DashAbility,
+	pub stamina             : components::Stamina,
 }
 
 impl GhostedSpriteBundle {
@@ -8,12 +8,14 @@ use crate::system;
 
 pub struct PlayerCameraPlugin;
 
+const BOSS_PHASE_SHAKE: f32 = 5.0;
+
 impl Plugin for PlayerCameraPlugin {
 	fn build(&self, app: &mut App) {
 		app.add_systems(Startup, setup)
 		.add_systems(
 			Update,
-			(apply_camera_shake, camera_follow_player, camera_shake_event_read).in_set(system::GameSet::Apply)
+			(apply_camera_shake, camera_follow_player, camera_shake_event_read, boss_phase_shake).in_set(system::GameSet::Apply)
 		);
 	}
 }
@@ -42,7 +44,6 @@ fn setup(mut commands: Commands) {
 				x:         1.0,
 				y:         1.0,
 				z:         1.0,
-				..default()
 			},
 		));
 	});
@@ -82,6 +83,17 @@ fn camera_shake_event_read(
 	}
 }
 
+//The boss roars when its phase changes
+fn boss_phase_shake(
+	mut er_phase: EventReader<events::BossPhaseEvent>,
+	mut query: Query<&mut components::CameraShake>,
+) {
+	let Ok(mut camera_shake) = query.get_single_mut() else { return };
+	for _ in er_phase.read() {
+		camera_shake.intensity += BOSS_PHASE_SHAKE;
+	}
+}
+
 fn camera_follow_player(
 	q_player: Query<&Transform, With<components::PlayerCharacter>>,
 	mut q_camera: Query<(&mut Transform, &components::CameraFollow), Without<components::PlayerCharacter>>,
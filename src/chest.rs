@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use std::borrow::Cow;
+
+use crate::actions;
+use crate::components;
+use crate::events;
+use crate::inventory;
+use crate::loot;
+use crate::map;
+use crate::save;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct ChestPlugin;
+
+impl Plugin for ChestPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Startup, spawn_chests.after(map::generate_map))
+		.add_systems(
+			Update,
+			open_chests.in_set(system::GameSet::CommandsAction).run_if(actions::action_just_pressed(actions::Action::Interact))
+		);
+	}
+}
+
+const CLOSED_TEXTURE : &str = "textures/rpg/props/generic-rpg-treasure-closed.png";
+const OPEN_TEXTURE   : &str = "textures/rpg/props/generic-rpg-trasure-open.png";
+const INTERACT_RANGE : f32 = 32.0;
+//Opened chests throw their loot around them in a ring of this radius
+const SCATTER_RADIUS : f32 = 24.0;
+const CHEST_SIZE     : Vec2 = Vec2::new(25.0, 12.0);
+
+//Chests stand at fixed spots inside the grass clearing around the spawn so their ids stay valid between runs
+const CHEST_SPOTS: [(u32, Vec2); 3] = [
+	(0, Vec2::new(-90.0, 60.0)),
+	(1, Vec2::new(90.0, 60.0)),
+	(2, Vec2::new(0.0, -100.0)),
+];
+
+const CHEST_DROPS: loot::DropTable = loot::DropTable {
+	rolls: 3,
+	entries: Cow::Borrowed(&[
+		(4.0, Some(loot::LootKind::Currency)),
+		(2.0, Some(loot::LootKind::Item(inventory::ItemId::HealthPotion))),
+		(1.0, Some(loot::LootKind::Item(inventory::ItemId::SpeedPotion))),
+		(1.0, Some(loot::LootKind::Item(inventory::ItemId::Spear))),
+		(1.0, Some(loot::LootKind::Item(inventory::ItemId::Sling))),
+		(1.0, Some(loot::LootKind::Item(inventory::ItemId::Shield))),
+		(1.0, Some(loot::LootKind::Item(inventory::ItemId::Wand))),
+	]),
+};
+
+pub fn spawn_chests(
+	mut commands: Commands,
+	r_asset_server: Res<AssetServer>,
+	r_save: Res<save::SaveData>,
+	mut rm_tile_metadata: ResMut<map::TileMetadata>,
+) {
+	for (id, position) in CHEST_SPOTS {
+		let opened = r_save.opened_chests.contains(&id);
+		let texture = if opened { OPEN_TEXTURE } else { CLOSED_TEXTURE };
+
+		commands.spawn((
+			SpriteBundle {
+				sprite: Sprite { anchor: Anchor::BottomCenter, ..default() },
+				transform: Transform::from_translation(position.extend(0.0)),
+				texture: r_asset_server.load(texture),
+				..default()
+			},
+			components::Chest { id, opened, drop_table: &CHEST_DROPS },
+			components::Intersect { size: CHEST_SIZE },
+			components::DepthSort { base: 900.0 },
+		));
+		rm_tile_metadata.set_area_blocked(position, CHEST_SIZE, true);
+	}
+}
+
+fn open_chests(
+	r_asset_server: Res<AssetServer>,
+	mut rm_save: ResMut<save::SaveData>,
+	mut q_chest: Query<(&mut components::Chest, &mut Handle<Image>, &Transform)>,
+	q_player: Query<&Transform, With<components::PlayerCharacter>>,
+	mut ew_loot_drop: EventWriter<events::LootDropEvent>,
+) {
+	let Ok(player_transform) = q_player.get_single() else { return };
+	let player_position = player_transform.translation.truncate();
+	let mut rng = rand::thread_rng();
+
+	for (mut chest, mut texture, transform) in q_chest.iter_mut() {
+		let position = transform.translation.truncate();
+		if chest.opened || position.distance(player_position) > INTERACT_RANGE {
+			continue;
+		}
+
+		chest.opened = true;
+		*texture = r_asset_server.load(OPEN_TEXTURE);
+		rm_save.opened_chests.push(chest.id);
+
+		let drops = chest.drop_table.roll(&mut rng);
+		let step = std::f32::consts::TAU / drops.len().max(1) as f32;
+		for (i, kind) in drops.into_iter().enumerate() {
+			let offset = Vec2::from_angle(step * i as f32) * SCATTER_RADIUS;
+			ew_loot_drop.send(events::LootDropEvent { kind, position: position + offset });
+		}
+	}
+}
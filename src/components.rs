@@ -1,5 +1,13 @@
 use bevy::prelude::*;
 
+use crate::boss;
+use crate::enemy;
+use crate::loot;
+use crate::player_weapon;
+use crate::projectile;
+use crate::steering;
+use crate::telegraph;
+
 #[derive(Clone, Default, Component)]
 pub struct WalkAnimate {
 	pub spd           : f32,
@@ -8,11 +16,114 @@ pub struct WalkAnimate {
 	pub ghosting_timer: Option<Timer>
 }
 
-#[derive(Clone, Default, Component)]
+#[derive(Clone, Component)]
 pub struct PlayerWeapon {
-	pub damage: f32,
+	pub def     : player_weapon::WeaponDef,
+	pub cooldown: Timer,
+	//direction from the player towards the cursor
+	pub aim     : Vec2,
+	//direction the weapon is held in, turning towards the aim
+	pub facing  : Vec2,
+	//seconds the attack has been held for, None while not charging
+	pub charge  : Option<f32>,
+	//steps of the combo done so far
+	pub combo   : usize,
+	//time left after a swing to continue the combo
+	pub combo_window: Timer,
+	pub secondary_cooldown: Timer,
+}
+
+//Paid for secondary attacks
+#[derive(Clone, Default, Component, Debug)]
+pub struct Focus {
+	pub current: f32,
+	pub max    : f32,
+}
+
+//Spent by dashing, charging attacks and blocking
+#[derive(Clone, Default, Component, Debug)]
+pub struct Stamina {
+	pub current    : f32,
+	pub max        : f32,
+	//per second
+	pub regen      : f32,
+	//seconds after spending before it starts to regen
+	pub regen_delay: f32,
+	pub delay      : Timer,
+}
+
+#[derive(Clone, Component)]
+pub struct StaminaMeter {
+	pub id   : Entity,
+	//the meter flashes while it runs, after an action was refused
+	pub flash: Timer,
 }
 
+#[derive(Clone, Component)]
+pub struct FocusMeter {
+	pub id: Entity,
+}
+
+//Hits from the front are reduced while it is on, or parried right after it starts
+#[derive(Clone, Default, Component)]
+pub struct Blocking {
+	pub reduction: f32,
+	pub facing   : Vec2,
+	//hits are parried until it finishes
+	pub parry    : Timer,
+	//of the weapon that started it, it starts once the block is let go
+	pub cooldown : f32,
+}
+
+//Dash of the player, the cooldown starts when the dash does
+#[derive(Clone, Component)]
+pub struct DashAbility {
+	pub cooldown: Timer,
+	//whether the player is invulnerable while dashing
+	pub iframes : bool,
+}
+
+impl Default for DashAbility {
+	fn default() -> Self {
+		Self { cooldown: Timer::default(), iframes: true }
+	}
+}
+
+//Added for the duration of a dash
+#[derive(Clone, Component)]
+pub struct Dashing {
+	pub velocity      : Vec2,
+	pub timer         : Timer,
+	//ghosting interval to go back to once the dash ends
+	pub ghost_interval: Option<bevy::utils::Duration>,
+}
+
+#[derive(Clone, Component)]
+pub struct DashMeter {
+	pub id: Entity,
+}
+
+//Takes no damage while it is on
+#[derive(Clone, Default, Component)]
+pub struct Invulnerable;
+
+//Can't move or deal contact damage until the timer finishes
+#[derive(Clone, Default, Component)]
+pub struct Stagger {
+	pub timer: Timer,
+}
+
+//Text showing the player's combo
+#[derive(Clone, Default, Component)]
+pub struct ComboCounter {
+	//combo the text was last written for
+	pub shown: usize,
+}
+
+//Sprite on the weapon showing how charged the attack is
+#[derive(Clone, Default, Component)]
+pub struct ChargeGlow;
+
 #[derive(PartialEq, Clone, Default, Component)]
 pub struct Intersect {
 	pub size: Vec2,
@@ -21,6 +132,7 @@ pub struct Intersect {
 #[derive(Clone, Default, Component, Debug)]
 pub struct Health {
 	pub current         : f32,
+	pub max             : f32,
 	pub unapplied_damage: f32,
 }
 
@@ -54,17 +166,202 @@ pub struct CameraShake {
 	pub z        : f32,
 }
 
+//On the boss side, their damage zones don't hurt each other
+#[derive(Clone, Default, Component)]
+pub struct Hostile;
+
 #[derive(Clone, Default, Component)]
 pub struct DamageZone {
 	pub damage: f32,
 	pub ignore: Option<Entity>,
 }
 
+//The z order is set from the y position so things lower on the screen are drawn on top
+#[derive(Clone, Default, Component)]
+pub struct DepthSort {
+	pub base: f32,
+}
+
+#[derive(Clone, Component)]
+pub struct Chest {
+	pub id        : u32,
+	pub opened    : bool,
+	pub drop_table: &'static loot::DropTable,
+}
+
+//Breaks once its health runs out, rolling the drop table
+#[derive(Clone, Component)]
+pub struct Destructible {
+	pub drop_table: &'static loot::DropTable,
+}
+
 #[derive(Clone, Default, Component)]
 pub struct Boss {
 	pub speed: f32,
 }
 
+//What the boss is doing and how long it keeps doing it
+#[derive(Clone, Default, Component)]
+pub struct BossBrain {
+	pub state    : boss::BossState,
+	pub timer    : Timer,
+	pub phase    : usize,
+	//index of the next move of the phase move set, they are used in order
+	pub next_move: usize,
+	pub current  : Option<boss::BossMove>,
+	//volleys of the current move already shot
+	pub fired    : u32,
+	//where the current move is aimed, kept once the windup ends
+	pub direction: Vec2,
+}
+
+impl BossBrain {
+	pub fn enter(&mut self, state: boss::BossState, seconds: f32) {
+		self.state = state;
+		self.timer = Timer::from_seconds(seconds, TimerMode::Once);
+	}
+}
+
+//Warning marker for an attack, fades in until the timer runs out
+#[derive(Clone, Component)]
+pub struct Telegraph {
+	pub shape: telegraph::TelegraphShape,
+	pub timer: Timer,
+	pub color: Color,
+	//the marker goes away if this entity does
+	pub owner: Option<Entity>,
+}
+
+//Waypoints towards the player, stored last to first so the next one can be popped
+#[derive(Clone, Default, Component)]
+pub struct PathFollower {
+	pub waypoints: Vec<Vec2>,
+	//where the player was when the path was searched
+	pub target   : Vec2,
+	//paths are searched at most once every time this runs out
+	pub cooldown : Timer,
+}
+
+impl PathFollower {
+	//None means the way is clear, or there is no way at all
+	pub fn heading(&self, position: Vec2) -> Option<Vec2> {
+		self.waypoints.last().map(|waypoint| (*waypoint - position).normalize_or_zero())
+	}
+}
+
+//Weighted steering behaviours, added up into where the entity wants to go
+#[derive(Clone, Default, Component)]
+pub struct Steering {
+	pub behaviours  : Vec<(f32, steering::SteeringBehaviour)>,
+	//where it was going last frame, obstacles are looked for along it
+	pub heading     : Vec2,
+	pub wander_angle: f32,
+}
+
+//Anything built from an archetype, the boss included
+#[derive(Clone, Component)]
+pub struct Enemy {
+	pub archetype: String,
+	pub speed    : f32,
+	pub behaviour: enemy::Behaviour,
+}
+
+//Push applied to the velocity every frame, fading until it is removed
+#[derive(Clone, Default, Component)]
+pub struct Knockback {
+	pub v    : Vec2,
+	pub decay: f32,
+}
+
+//Something on the ground that is collected when the player touches it
+#[derive(Clone, Component)]
+pub struct Pickup {
+	pub kind : loot::LootKind,
+	pub delay: Timer,
+}
+
+//Moves a sprite up and down around its original position
+#[derive(Clone, Default, Component)]
+pub struct Bob {
+	pub amplitude: f32,
+	pub speed    : f32,
+	pub phase    : f32,
+}
+
+#[derive(Clone, Default, Component)]
+pub struct Wallet {
+	pub coins: u32,
+}
+
+//Temporary speed boost, removed once the timer finishes
+#[derive(Clone, Default, Component)]
+pub struct SpeedBuff {
+	pub multiplier: f32,
+	pub timer     : Timer,
+}
+
+#[derive(Clone, Component)]
+pub struct CoinCounter {
+	pub id: Entity,
+}
+
+//Attack in progress on a weapon, removed once it has recovered
+#[derive(Clone, Component)]
+pub struct Swing {
+	pub def      : player_weapon::WeaponDef,
+	pub phase    : player_weapon::SwingPhase,
+	pub timer    : Timer,
+	//aim when the swing started, the swing keeps it even if the cursor moves
+	pub direction: Vec2,
+	//targets already damaged, each one is only hit once per swing
+	pub hit      : Vec<Entity>,
+	//released at full charge
+	pub heavy    : bool,
+	pub animation: player_weapon::SwingAnimation,
+}
+
+impl PlayerWeapon {
+	pub fn new(def: player_weapon::WeaponDef) -> Self {
+		Self { def, cooldown: Timer::default(), aim: Vec2::Y, facing: Vec2::Y, charge: None, combo: 0, combo_window: Timer::default(), secondary_cooldown: Timer::default() }
+	}
+}
+
+impl Health {
+	pub fn new(amount: f32) -> Self {
+		Self { current: amount, max: amount, unapplied_damage: 0.0 }
+	}
+
+	pub fn heal(&mut self, amount: f32) {
+		self.current = (self.current + amount).min(self.max);
+	}
+}
+
+impl Focus {
+	pub fn new(amount: f32) -> Self {
+		Self { current: amount, max: amount }
+	}
+
+	pub fn gain(&mut self, amount: f32) {
+		self.current = (self.current + amount).min(self.max);
+	}
+}
+
+impl Stamina {
+	pub fn new(amount: f32, regen: f32, regen_delay: f32) -> Self {
+		Self { current: amount, max: amount, regen, regen_delay, delay: Timer::default() }
+	}
+
+	//Nothing is spent if there is not enough
+	pub fn spend(&mut self, amount: f32) -> bool {
+		if self.current < amount {
+			return false;
+		}
+		self.current -= amount;
+		self.delay = Timer::from_seconds(self.regen_delay, TimerMode::Once);
+		true
+	}
+}
+
 impl DespawnerTimer {
 	pub fn new(despawn_time: bevy::utils::Duration) -> Self {
 		Self { timer: Timer::new(despawn_time, TimerMode::Once) }
@@ -75,8 +372,8 @@ impl WalkAnimate {
 	pub fn new(speed: f32, intensity: f32, step_intensity: f32) -> Self {
 		Self { 
 			spd: speed,
-			intensity: intensity,
-			step_intensity: step_intensity,
+			intensity,
+			step_intensity,
 			ghosting_timer: None 
 		}
 	}
@@ -84,9 +381,21 @@ impl WalkAnimate {
 	pub fn new_with_marker(speed: f32, intensity: f32, step_intensity: f32, tracking_time: bevy::utils::Duration) -> Self {
 		Self {
 			spd: speed,
-			intensity: intensity,
-			step_intensity: step_intensity,
+			intensity,
+			step_intensity,
 			ghosting_timer: Some(Timer::new(tracking_time, TimerMode::Repeating))
 		}
 	}
 }
+
+//Flies along its direction until it runs out of pierce, hits a blocked tile or its timer runs out
+#[derive(Clone, Component)]
+pub struct Projectile {
+	pub def      : projectile::ProjectileDef,
+	pub direction: Vec2,
+	pub hostile  : bool,
+	//who shot it, staggered when it gets parried
+	pub owner    : Option<Entity>,
+	//targets already hit, so piercing projectiles don't hit the same one every frame
+	pub pierced  : Vec<Entity>,
+}
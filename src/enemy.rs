@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+
+use std::path::{Path, PathBuf};
+
+use crate::components;
+use crate::events;
+use crate::loot;
+use crate::miscellaneous;
+use crate::steering::SteeringBehaviour;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+	fn build(&self, app: &mut App) {
+		app.insert_resource(EnemyArchetypes {
+			folder: miscellaneous::asset_folder(app).join(ARCHETYPES_FOLDER),
+			..default()
+		})
+		.add_systems(PreStartup, load_archetypes)
+		.add_systems(Update, enemy_death.in_set(system::GameSet::CommandsAction));
+	}
+}
+
+//Every file in here is an archetype, its id is the file name without the extension
+const ARCHETYPES_FOLDER: &str = "enemies";
+
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+pub enum Behaviour {
+	//always goes after the player
+	Chase,
+	//only goes after the player while it is within `range`
+	Guard { range: f32 },
+	//left to the boss state machine
+	Boss,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EnemyArchetype {
+	//sprite sheet with all frames on one row
+	pub texture  : String,
+	pub frame    : Vec2,
+	pub columns  : usize,
+	//how much bigger than a frame the sprite is drawn
+	#[serde(default = "default_scale")]
+	pub scale    : f32,
+	pub health   : f32,
+	pub speed    : f32,
+	pub collider : Vec2,
+	//applied every frame the player is touched
+	pub damage   : f32,
+	pub behaviour: Behaviour,
+	//how it moves while not left to the boss state machine
+	#[serde(default = "default_steering")]
+	pub steering : Vec<(f32, SteeringBehaviour)>,
+	#[serde(default)]
+	pub walk     : WalkAnimation,
+	#[serde(default)]
+	pub drops    : loot::DropTable,
+	//color of the burst left behind when it dies
+	pub color    : Color,
+}
+
+//How the sprite bobs while it moves, see components::WalkAnimate
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+pub struct WalkAnimation {
+	pub speed         : f32,
+	pub intensity     : f32,
+	pub step_intensity: f32,
+}
+
+impl Default for WalkAnimation {
+	fn default() -> Self {
+		Self { speed: 20.0, intensity: 1.0, step_intensity: 2.0 }
+	}
+}
+
+fn default_scale() -> f32 {
+	1.0
+}
+
+fn default_steering() -> Vec<(f32, SteeringBehaviour)> {
+	vec![(1.0, SteeringBehaviour::Seek)]
+}
+
+#[derive(Clone, Default, Resource)]
+pub struct EnemyArchetypes {
+	folder    : PathBuf,
+	archetypes: HashMap<String, EnemyArchetype>,
+	atlases   : HashMap<String, Handle<TextureAtlas>>,
+}
+
+impl EnemyArchetypes {
+	pub fn get(&self, id: &str) -> Option<&EnemyArchetype> {
+		self.archetypes.get(id)
+	}
+}
+
+fn read_archetype(path: &Path) -> Result<EnemyArchetype, String> {
+	let contents = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+	ron::from_str(&contents).map_err(|error| error.to_string())
+}
+
+//Broken files are skipped, anything asking for them spawns nothing
+fn load_archetypes(
+	mut rm_archetypes: ResMut<EnemyArchetypes>,
+	r_asset_server: Res<AssetServer>,
+	mut rm_texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+	let archetypes = rm_archetypes.as_mut();
+	let entries = match std::fs::read_dir(&archetypes.folder) {
+		Ok(entries) => entries,
+		Err(error) => {
+			warn!("could not read {}: {error}", archetypes.folder.display());
+			return;
+		}
+	};
+
+	for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+		if path.extension().map_or(true, |extension| extension != "ron") {
+			continue;
+		}
+		let Some(id) = path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string) else { continue };
+		let archetype = match read_archetype(&path) {
+			Ok(archetype) => archetype,
+			Err(error) => {
+				warn!("could not load enemy archetype {}: {error}", path.display());
+				continue;
+			}
+		};
+
+		let atlas = TextureAtlas::from_grid(r_asset_server.load(&archetype.texture), archetype.frame, archetype.columns, 1, None, None);
+		archetypes.atlases.insert(id.clone(), rm_texture_atlases.add(atlas));
+		archetypes.archetypes.insert(id, archetype);
+	}
+}
+
+pub fn spawn_enemy(commands: &mut Commands, r_archetypes: &EnemyArchetypes, id: &str, position: Vec2) -> Option<Entity> {
+	let Some(archetype) = r_archetypes.get(id) else {
+		warn!("unknown enemy archetype {id}");
+		return None;
+	};
+
+	let mut enemy = commands.spawn((
+		SpatialBundle {
+			transform: Transform::from_translation(position.extend(900.0)),
+			..default()
+		},
+		components::Velocity::default(),
+		components::Health::new(archetype.health),
+		components::Intersect { size: archetype.collider },
+		components::DamageZone { damage: archetype.damage, ignore: None },
+		components::Enemy { archetype: id.to_string(), speed: archetype.speed, behaviour: archetype.behaviour },
+		components::Hostile,
+		components::WalkAnimate::new(archetype.walk.speed, archetype.walk.intensity, archetype.walk.step_intensity),
+		components::DepthSort { base: 900.0 },
+	));
+	if archetype.behaviour == Behaviour::Boss {
+		enemy.insert((
+			components::Boss { speed: archetype.speed },
+			components::BossBrain::default(),
+			components::PathFollower::default(),
+		));
+	} else {
+		enemy.insert(components::Steering { behaviours: archetype.steering.clone(), ..default() });
+	}
+
+	enemy.with_children(|parent| {
+		parent.spawn(SpriteSheetBundle {
+			texture_atlas: r_archetypes.atlases.get(id).cloned().unwrap_or_default(),
+			sprite: TextureAtlasSprite {
+				custom_size: Some(archetype.frame * archetype.scale),
+				index: 0,
+				..default()
+			},
+			..default()
+		});
+	});
+	Some(enemy.id())
+}
+
+//The boss rolls its drops here too, but its despawn is left to the victory screen
+fn enemy_death(
+	mut commands: Commands,
+	r_archetypes: Res<EnemyArchetypes>,
+	q_enemy: Query<(Entity, &Transform, &components::Health, &components::Enemy)>,
+	mut ew_loot_drop: EventWriter<events::LootDropEvent>,
+) {
+	let mut rng = rand::thread_rng();
+	for (id, transform, health, enemy) in q_enemy.iter() {
+		if health.current > 0.0 {
+			continue;
+		}
+		let Some(archetype) = r_archetypes.get(&enemy.archetype) else { continue };
+
+		let position = transform.translation.truncate();
+		for kind in archetype.drops.roll(&mut rng) {
+			ew_loot_drop.send(events::LootDropEvent { kind, position });
+		}
+		if enemy.behaviour == Behaviour::Boss {
+			continue;
+		}
+
+		miscellaneous::generic_particle_burst(
+			&mut commands,
+			transform.translation,
+			100.0,
+			0.4,
+			1.5,
+			20,
+			(archetype.color..archetype.color.with_a(0.0)).into()
+		);
+		commands.entity(id).despawn_recursive();
+	}
+}
@@ -1,5 +1,9 @@
 use bevy::ecs::event::Event;
 use bevy::ecs::entity::Entity;
+use bevy::math::Vec2;
+
+use crate::inventory;
+use crate::loot;
 
 #[derive(Clone, Default, Event)]
 pub struct ShakeEvent {
@@ -10,3 +14,60 @@ pub struct ShakeEvent {
 pub struct IntersectEvent {
 	pub ab: (Entity, Entity),
 }
+
+#[derive(Clone, Event)]
+pub struct PickedUpEvent {
+	pub collector: Entity,
+	pub kind     : loot::LootKind,
+}
+
+#[derive(Clone, Event)]
+pub struct ItemUsedEvent {
+	pub user: Entity,
+	pub item: inventory::ItemId,
+}
+
+#[derive(Clone, Event)]
+pub struct LootDropEvent {
+	pub kind    : loot::LootKind,
+	pub position: Vec2,
+}
+
+//Sent when an action is refused because there is not enough stamina
+#[derive(Clone, Event)]
+pub struct ExhaustedEvent {
+	pub id: Entity,
+}
+
+//Sent when a hit is parried, position is where the parry happened
+#[derive(Clone, Event)]
+pub struct ParryEvent {
+	pub attacker: Option<Entity>,
+	pub position: Vec2,
+}
+
+//Sent when damage is applied to something with health
+#[derive(Clone, Event)]
+pub struct DamagedEvent {
+	pub target: Entity,
+}
+
+//Sent when a wave starts spawning, wave is its index
+#[derive(Clone, Event)]
+pub struct WaveStartedEvent {
+	pub wave: usize,
+}
+
+//Sent when every enemy of a wave is dead, last is set for the final wave
+#[derive(Clone, Event)]
+pub struct WaveClearedEvent {
+	pub wave: usize,
+	pub last: bool,
+}
+
+//Sent when the boss moves on to the next phase, phase is the index of the new one
+#[derive(Clone, Event)]
+pub struct BossPhaseEvent {
+	pub id   : Entity,
+	pub phase: usize,
+}
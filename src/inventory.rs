@@ -0,0 +1,628 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::actions;
+use crate::components;
+use crate::events;
+use crate::loot;
+use crate::player_weapon;
+use crate::player_weapon::{ComboStep, HitShape, SecondaryDef, SecondaryKind, SwingAnimation, SwingTiming, WeaponDef, WeaponKind, WeaponOrbit, WeaponVfx};
+use crate::projectile::{OnHit, ProjectileDef};
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct InventoryPlugin;
+
+impl Plugin for InventoryPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<ItemRegistry>()
+		.init_resource::<InventoryUi>()
+		.add_systems(Startup, setup_ui)
+		.add_systems(Update, toggle_ui.run_if(actions::action_just_pressed(actions::Action::Inventory)).in_set(system::GameSet::Check))
+		.add_systems(Update, slot_interaction.in_set(system::GameSet::CommandsAction))
+		.add_systems(Update, (apply_item_effects, equip_weapon).in_set(system::GameSet::SingleAction))
+		.add_systems(Update, (refresh_ui, item_name_label).in_set(system::GameSet::Apply));
+	}
+}
+
+pub const INVENTORY_SLOTS: usize = 12;
+//One for each weapon hotkey
+pub const MAX_EQUIPPED   : usize = 4;
+const SLOT_COLUMNS       : usize = 4;
+const UI_SCALE           : f32 = 4.0;
+const PANEL_TEXTURE      : &str = "textures/rpg/ui/generic-rpg-ui-inventario.png";
+const SLOT_TEXTURE       : &str = "textures/rpg/ui/generic-rpg-ui-inventario01.png";
+const EQUIPPED_TEXTURE   : &str = "textures/rpg/ui/generic-rpg-ui-inventario02.png";
+//Dropped items can't be picked up again right away
+const DROP_DELAY         : f32 = 3.0;
+const DROP_OFFSET        : Vec2 = Vec2::new(0.0, -24.0);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum ItemId {
+	HealthPotion,
+	SpeedPotion,
+	Sword,
+	Spear,
+	Sling,
+	Shield,
+	Wand,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ItemEffect {
+	Heal(f32),
+	SpeedBuff { multiplier: f32, seconds: f32 },
+}
+
+//Only the registry and lookups hold these, the weapon's size never ends up in a component
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ItemKind {
+	Consumable(ItemEffect),
+	Weapon(WeaponDef),
+}
+
+#[derive(Clone, Debug)]
+pub struct ItemDef {
+	pub name     : &'static str,
+	pub texture  : &'static str,
+	pub max_stack: u32,
+	pub kind     : ItemKind,
+}
+
+//Every item that can be held, looked up by id
+#[derive(Clone, Resource)]
+pub struct ItemRegistry {
+	items: HashMap<ItemId, ItemDef>,
+}
+
+impl ItemRegistry {
+	pub fn register(&mut self, id: ItemId, def: ItemDef) {
+		self.items.insert(id, def);
+	}
+
+	pub fn get(&self, id: ItemId) -> Option<&ItemDef> {
+		self.items.get(&id)
+	}
+}
+
+impl Default for ItemRegistry {
+	fn default() -> Self {
+		let mut registry = Self { items: HashMap::default() };
+		registry.register(ItemId::HealthPotion, ItemDef {
+			name     : "Health Potion",
+			texture  : "textures/rpg/props/generic-rpg-loot03.png",
+			max_stack: 5,
+			kind     : ItemKind::Consumable(ItemEffect::Heal(25.0)),
+		});
+		registry.register(ItemId::SpeedPotion, ItemDef {
+			name     : "Speed Potion",
+			texture  : "textures/rpg/props/generic-rpg-loot04.png",
+			max_stack: 5,
+			kind     : ItemKind::Consumable(ItemEffect::SpeedBuff { multiplier: 1.5, seconds: 8.0 }),
+		});
+		registry.register(ItemId::Sword, ItemDef {
+			name     : "Sword",
+			texture  : "textures/rpg/props/generic-rpg-loot01.png",
+			max_stack: 1,
+			kind     : ItemKind::Weapon(WeaponDef {
+				damage: 8.0,
+				secondary: Some(SecondaryDef {
+					kind    : SecondaryKind::Block { reduction: 0.5 },
+					cooldown: 0.5,
+					cost    : 10.0,
+					color   : Color::WHITE,
+				}),
+				..player_weapon::DEFAULT_WEAPON
+			}),
+		});
+		registry.register(ItemId::Spear, ItemDef {
+			name     : "Spear",
+			texture  : "textures/rpg/props/generic-rpg-rod.png",
+			max_stack: 1,
+			kind     : ItemKind::Weapon(WeaponDef {
+				kind     : WeaponKind::Thrust,
+				texture  : "textures/rpg/props/generic-rpg-rod.png",
+				texture_angle: std::f32::consts::FRAC_PI_2,
+				damage   : 10.0,
+				cooldown : 0.5,
+				timing   : SwingTiming { windup: 0.12, active: 0.1, recovery: 0.18 },
+				knockback: 150.0,
+				hitbox   : HitShape::Line { length: 60.0, width: 12.0 },
+				vfx      : WeaponVfx { color: Color::SILVER, count: 30, speed: 150.0, scale: 1.0, shake: 1.0 },
+				orbit    : WeaponOrbit { radius: 26.0, smoothing: 14.0 },
+				combo    : &[
+					ComboStep { damage: 1.0, hitbox: None, animation: SwingAnimation::Stab },
+					ComboStep { damage: 1.0, hitbox: None, animation: SwingAnimation::Stab },
+					ComboStep { damage: 1.4, hitbox: Some(HitShape::Arc { radius: 56.0, half_angle: 1.2 }), animation: SwingAnimation::Slash },
+				],
+				secondary: Some(SecondaryDef {
+					kind    : SecondaryKind::Shockwave { radius: 60.0, damage: 6.0, knockback: 250.0 },
+					cooldown: 2.0,
+					cost    : 35.0,
+					color   : Color::SILVER,
+				}),
+			}),
+		});
+		registry.register(ItemId::Sling, ItemDef {
+			name     : "Sling",
+			texture  : "textures/rpg/props/generic-rpg-rock01.png",
+			max_stack: 1,
+			kind     : ItemKind::Weapon(WeaponDef {
+				kind     : WeaponKind::Projectile(ProjectileDef {
+					speed   : 300.0,
+					lifetime: 1.0,
+					damage  : 6.0,
+					size    : 8.0,
+					color   : Color::GRAY,
+					pierce  : 0,
+					homing  : 0.0,
+					on_hit  : OnHit::Knockback(80.0),
+				}),
+				texture  : "textures/rpg/props/generic-rpg-rock01.png",
+				texture_angle: 0.0,
+				damage   : 0.0,
+				cooldown : 0.4,
+				timing   : SwingTiming { windup: 0.08, active: 0.05, recovery: 0.15 },
+				knockback: 0.0,
+				hitbox   : HitShape::Circle { radius: 4.0 },
+				vfx      : WeaponVfx { color: Color::GRAY, count: 15, speed: 80.0, scale: 1.0, shake: 0.5 },
+				orbit    : WeaponOrbit { radius: 20.0, smoothing: 25.0 },
+				combo    : &[],
+				secondary: None,
+			}),
+		});
+		registry.register(ItemId::Shield, ItemDef {
+			name     : "Shield",
+			texture  : "textures/rpg/props/generic-rpg-loot02.png",
+			max_stack: 1,
+			kind     : ItemKind::Weapon(WeaponDef {
+				kind     : WeaponKind::AoeSlam,
+				texture  : "textures/rpg/props/generic-rpg-loot02.png",
+				texture_angle: std::f32::consts::FRAC_PI_2,
+				damage   : 12.0,
+				cooldown : 1.2,
+				timing   : SwingTiming { windup: 0.35, active: 0.12, recovery: 0.4 },
+				knockback: 300.0,
+				hitbox   : HitShape::Circle { radius: 50.0 },
+				vfx      : WeaponVfx { color: Color::ORANGE, count: 150, speed: 250.0, scale: 2.0, shake: 3.0 },
+				orbit    : WeaponOrbit { radius: 22.0, smoothing: 8.0 },
+				combo    : &[],
+				secondary: Some(SecondaryDef {
+					kind    : SecondaryKind::Block { reduction: 0.7 },
+					cooldown: 0.4,
+					cost    : 10.0,
+					color   : Color::ORANGE,
+				}),
+			}),
+		});
+		registry.register(ItemId::Wand, ItemDef {
+			name     : "Wand",
+			texture  : "textures/rpg/props/generic-rpg-rod.png",
+			max_stack: 1,
+			kind     : ItemKind::Weapon(WeaponDef {
+				kind     : WeaponKind::Projectile(ProjectileDef {
+					speed   : 180.0,
+					lifetime: 2.0,
+					damage  : 4.0,
+					size    : 6.0,
+					color   : Color::PURPLE,
+					pierce  : 1,
+					homing  : 6.0,
+					on_hit  : OnHit::Explode { radius: 30.0, damage: 3.0 },
+				}),
+				texture  : "textures/rpg/props/generic-rpg-rod.png",
+				texture_angle: std::f32::consts::FRAC_PI_2,
+				damage   : 0.0,
+				cooldown : 0.8,
+				timing   : SwingTiming { windup: 0.2, active: 0.05, recovery: 0.3 },
+				knockback: 0.0,
+				hitbox   : HitShape::Circle { radius: 3.0 },
+				vfx      : WeaponVfx { color: Color::PURPLE, count: 25, speed: 60.0, scale: 1.0, shake: 0.3 },
+				orbit    : WeaponOrbit { radius: 24.0, smoothing: 10.0 },
+				combo    : &[],
+				secondary: Some(SecondaryDef {
+					kind    : SecondaryKind::Shockwave { radius: 80.0, damage: 10.0, knockback: 350.0 },
+					cooldown: 3.0,
+					cost    : 50.0,
+					color   : Color::PURPLE,
+				}),
+			}),
+		});
+		registry
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ItemStack {
+	pub item : ItemId,
+	pub count: u32,
+}
+
+//Equipped weapons are slot indexes, in the order of the weapon hotkeys
+#[derive(Clone, Component)]
+pub struct Inventory {
+	pub slots   : Vec<Option<ItemStack>>,
+	pub equipped: Vec<usize>,
+	//index into equipped of the weapon in hand
+	pub active  : usize,
+}
+
+impl Default for Inventory {
+	fn default() -> Self {
+		Self { slots: vec![None; INVENTORY_SLOTS], equipped: Vec::new(), active: 0 }
+	}
+}
+
+impl Inventory {
+	//Fills existing stacks first, then the first empty slot
+	pub fn add(&mut self, item: ItemId, registry: &ItemRegistry) -> bool {
+		let Some(def) = registry.get(item) else { return false };
+
+		let stack = self.slots.iter_mut()
+			.flatten()
+			.find(|stack| stack.item == item && stack.count < def.max_stack);
+		if let Some(stack) = stack {
+			stack.count += 1;
+			return true;
+		}
+
+		let Some(slot) = self.slots.iter_mut().find(|slot| slot.is_none()) else { return false };
+		*slot = Some(ItemStack { item, count: 1 });
+		true
+	}
+
+	//Removes one item from the slot, unequipping it if the slot is emptied
+	pub fn take_one(&mut self, slot: usize) -> Option<ItemId> {
+		let stack = self.slots.get_mut(slot)?.as_mut()?;
+		let item = stack.item;
+		stack.count -= 1;
+		if stack.count == 0 {
+			self.slots[slot] = None;
+			self.unequip(slot);
+		}
+		Some(item)
+	}
+
+	//Equips the slot if there is room, unequips it if it already was
+	pub fn toggle_equipped(&mut self, slot: usize) {
+		if self.equipped.contains(&slot) {
+			self.unequip(slot);
+		} else if self.equipped.len() < MAX_EQUIPPED {
+			self.equipped.push(slot);
+		}
+	}
+
+	//The weapon in hand stays the same unless it is the one unequipped, then the next one is taken
+	fn unequip(&mut self, slot: usize) {
+		let Some(index) = self.equipped.iter().position(|equipped| *equipped == slot) else { return };
+		self.equipped.remove(index);
+		if index < self.active {
+			self.active -= 1;
+		} else if self.active >= self.equipped.len() {
+			self.active = 0;
+		}
+	}
+
+	pub fn active_item(&self) -> Option<ItemId> {
+		self.equipped.get(self.active).and_then(|slot| self.slots[*slot]).map(|stack| stack.item)
+	}
+}
+
+#[derive(Clone, Default, Resource)]
+pub struct InventoryUi {
+	pub open: bool,
+}
+
+pub fn inventory_closed(r_inventory_ui: Res<InventoryUi>) -> bool {
+	!r_inventory_ui.open
+}
+
+#[derive(Clone, Component)]
+struct InventoryRoot;
+
+#[derive(Clone, Component)]
+struct InventorySlot(usize);
+
+#[derive(Clone, Component)]
+struct SlotIcon(usize);
+
+#[derive(Clone, Component)]
+struct SlotCount(usize);
+
+#[derive(Clone, Component)]
+struct ItemNameLabel;
+
+fn setup_ui(
+	mut commands: Commands,
+	r_asset_server: Res<AssetServer>,
+) {
+	let slot_texture = r_asset_server.load(SLOT_TEXTURE);
+
+	commands.spawn((
+		NodeBundle {
+			style: Style {
+				position_type: PositionType::Absolute,
+				width: Val::Percent(100.0),
+				height: Val::Percent(100.0),
+				flex_direction: FlexDirection::Column,
+				justify_content: JustifyContent::Center,
+				align_items: AlignItems::Center,
+				display: Display::None,
+				..default()
+			},
+			..default()
+		},
+		InventoryRoot,
+	))
+	.with_children(|parent| {
+		parent.spawn(ImageBundle {
+			style: Style {
+				width: Val::Px(92.0 * UI_SCALE),
+				height: Val::Px(77.0 * UI_SCALE),
+				display: Display::Grid,
+				grid_template_columns: RepeatedGridTrack::auto(SLOT_COLUMNS as u16),
+				justify_content: JustifyContent::Center,
+				align_content: AlignContent::Center,
+				column_gap: Val::Px(2.0 * UI_SCALE),
+				row_gap: Val::Px(2.0 * UI_SCALE),
+				..default()
+			},
+			image: UiImage::new(r_asset_server.load(PANEL_TEXTURE)),
+			..default()
+		})
+		.with_children(|parent| {
+			for i in 0..INVENTORY_SLOTS {
+				parent.spawn((
+					ButtonBundle {
+						style: Style {
+							width: Val::Px(14.0 * UI_SCALE),
+							height: Val::Px(15.0 * UI_SCALE),
+							justify_content: JustifyContent::Center,
+							align_items: AlignItems::Center,
+							..default()
+						},
+						image: UiImage::new(slot_texture.clone()),
+						..default()
+					},
+					InventorySlot(i),
+				))
+				.with_children(|parent| {
+					parent.spawn((
+						ImageBundle {
+							style: Style {
+								width: Val::Px(10.0 * UI_SCALE),
+								height: Val::Px(10.0 * UI_SCALE),
+								..default()
+							},
+							visibility: Visibility::Hidden,
+							..default()
+						},
+						SlotIcon(i),
+					));
+					parent.spawn((
+						TextBundle::from_section(
+							"",
+							TextStyle {
+								font_size: 20.0,
+								color: Color::WHITE,
+								..default()
+							},
+						)
+						.with_style(Style {
+							position_type: PositionType::Absolute,
+							right: Val::Px(2.0),
+							bottom: Val::Px(0.0),
+							..default()
+						}),
+						SlotCount(i),
+					));
+				});
+			}
+		});
+
+		parent.spawn((
+			TextBundle::from_section(
+				"",
+				TextStyle {
+					font_size: 30.0,
+					color: Color::WHITE,
+					..default()
+				},
+			),
+			ItemNameLabel,
+		));
+	});
+}
+
+fn toggle_ui(
+	mut rm_inventory_ui: ResMut<InventoryUi>,
+	mut q_root: Query<&mut Style, With<InventoryRoot>>,
+) {
+	rm_inventory_ui.open = !rm_inventory_ui.open;
+	for mut style in q_root.iter_mut() {
+		style.display = if rm_inventory_ui.open { Display::Flex } else { Display::None };
+	}
+}
+
+//Using a slot uses consumables and equips weapons, dropping drops one item of the slot
+//Both act on the slot under the cursor
+fn slot_interaction(
+	mut commands: Commands,
+	r_asset_server: Res<AssetServer>,
+	r_registry: Res<ItemRegistry>,
+	r_actions: Res<actions::ActionState>,
+	q_slot: Query<(&Interaction, &InventorySlot)>,
+	mut q_player: Query<(Entity, &mut Inventory, &Transform), With<components::PlayerCharacter>>,
+	mut ew_item_used: EventWriter<events::ItemUsedEvent>,
+) {
+	let Ok((player_id, mut inventory, transform)) = q_player.get_single_mut() else { return };
+
+	for (interaction, slot) in q_slot.iter() {
+		let Some(stack) = inventory.slots[slot.0] else { continue };
+		if *interaction == Interaction::None {
+			continue;
+		}
+
+		if r_actions.just_pressed(actions::Action::UseItem) {
+			let Some(def) = r_registry.get(stack.item) else { continue };
+			match def.kind {
+				ItemKind::Consumable(_) => {
+					inventory.take_one(slot.0);
+					ew_item_used.send(events::ItemUsedEvent { user: player_id, item: stack.item });
+				},
+				ItemKind::Weapon(_) => inventory.toggle_equipped(slot.0),
+			}
+		}
+
+		if r_actions.just_pressed(actions::Action::DropItem) {
+			let Some(item) = inventory.take_one(slot.0) else { continue };
+			loot::spawn_pickup(
+				&mut commands,
+				&r_asset_server,
+				&r_registry,
+				loot::LootKind::Item(item),
+				transform.translation.truncate() + DROP_OFFSET,
+				DROP_DELAY,
+			);
+		}
+	}
+}
+
+fn apply_item_effects(
+	mut commands: Commands,
+	r_registry: Res<ItemRegistry>,
+	mut er_item_used: EventReader<events::ItemUsedEvent>,
+	mut q_health: Query<&mut components::Health>,
+) {
+	for event in er_item_used.read() {
+		let Some(ItemKind::Consumable(effect)) = r_registry.get(event.item).map(|def| &def.kind) else { continue };
+
+		match *effect {
+			ItemEffect::Heal(amount) => {
+				let Ok(mut health) = q_health.get_mut(event.user) else { continue };
+				health.heal(amount);
+			},
+			ItemEffect::SpeedBuff { multiplier, seconds } => {
+				commands.entity(event.user).insert(components::SpeedBuff {
+					multiplier,
+					timer: Timer::from_seconds(seconds, TimerMode::Once),
+				});
+			},
+		}
+	}
+}
+
+//The weapon in hand follows the active equipped weapon item
+fn equip_weapon(
+	r_asset_server: Res<AssetServer>,
+	r_registry: Res<ItemRegistry>,
+	q_inventory: Query<&Inventory, (With<components::PlayerCharacter>, Changed<Inventory>)>,
+	mut q_weapon: Query<(&mut components::PlayerWeapon, &mut Handle<Image>)>,
+) {
+	let Ok(inventory) = q_inventory.get_single() else { return };
+	let def = match inventory.active_item().and_then(|item| r_registry.get(item)).map(|def| &def.kind) {
+		Some(ItemKind::Weapon(def)) => *def,
+		_ => player_weapon::DEFAULT_WEAPON,
+	};
+
+	for (mut weapon, mut texture) in q_weapon.iter_mut() {
+		if weapon.def == def {
+			continue;
+		}
+		weapon.def = def;
+		*texture = r_asset_server.load(def.texture);
+	}
+}
+
+fn refresh_ui(
+	r_asset_server: Res<AssetServer>,
+	r_registry: Res<ItemRegistry>,
+	q_inventory: Query<&Inventory, (With<components::PlayerCharacter>, Changed<Inventory>)>,
+	mut q_slot: Query<(&mut UiImage, &InventorySlot), Without<SlotIcon>>,
+	mut q_icon: Query<(&mut UiImage, &mut Visibility, &SlotIcon), Without<InventorySlot>>,
+	mut q_count: Query<(&mut Text, &SlotCount)>,
+) {
+	let Ok(inventory) = q_inventory.get_single() else { return };
+
+	for (mut image, slot) in q_slot.iter_mut() {
+		let texture = if inventory.equipped.contains(&slot.0) { EQUIPPED_TEXTURE } else { SLOT_TEXTURE };
+		image.texture = r_asset_server.load(texture);
+	}
+
+	for (mut image, mut visibility, icon) in q_icon.iter_mut() {
+		match inventory.slots[icon.0].and_then(|stack| r_registry.get(stack.item)) {
+			Some(def) => {
+				image.texture = r_asset_server.load(def.texture);
+				*visibility = Visibility::Inherited;
+			},
+			None => *visibility = Visibility::Hidden,
+		}
+	}
+
+	for (mut text, count) in q_count.iter_mut() {
+		text.sections[0].value = match inventory.slots[count.0] {
+			Some(stack) if stack.count > 1 => stack.count.to_string(),
+			_ => String::new(),
+		};
+	}
+}
+
+//Shows the name of the item under the cursor
+fn item_name_label(
+	r_registry: Res<ItemRegistry>,
+	q_slot: Query<(&Interaction, &InventorySlot)>,
+	q_inventory: Query<&Inventory, With<components::PlayerCharacter>>,
+	mut q_label: Query<&mut Text, With<ItemNameLabel>>,
+) {
+	let (Ok(inventory), Ok(mut text)) = (q_inventory.get_single(), q_label.get_single_mut()) else { return };
+
+	let name = q_slot.iter()
+		.find(|(interaction, _)| **interaction != Interaction::None)
+		.and_then(|(_, slot)| inventory.slots[slot.0])
+		.and_then(|stack| r_registry.get(stack.item))
+		.map_or("", |def| def.name);
+
+	if text.sections[0].value != name {
+		text.sections[0].value = name.to_string();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	//Three weapons equipped from the first three slots, the second one in hand
+	fn inventory() -> Inventory {
+		let mut inventory = Inventory::default();
+		for (slot, item) in [ItemId::Sword, ItemId::Spear, ItemId::Sling].into_iter().enumerate() {
+			inventory.slots[slot] = Some(ItemStack { item, count: 1 });
+			inventory.toggle_equipped(slot);
+		}
+		inventory.active = 1;
+		inventory
+	}
+
+	#[test]
+	fn unequipping_before_the_active_weapon_keeps_it_in_hand() {
+		let mut inventory = inventory();
+		inventory.toggle_equipped(0);
+		assert_eq!(inventory.equipped, vec![1, 2]);
+		assert_eq!(inventory.active, 0);
+		assert_eq!(inventory.active_item(), Some(ItemId::Spear));
+
+		inventory.toggle_equipped(2);
+		assert_eq!(inventory.active_item(), Some(ItemId::Spear));
+	}
+
+	#[test]
+	fn unequipping_the_last_active_weapon_goes_back_to_the_first() {
+		let mut inventory = inventory();
+		inventory.active = 2;
+		assert_eq!(inventory.take_one(2), Some(ItemId::Sling));
+		assert_eq!(inventory.equipped, vec![0, 1]);
+		assert_eq!(inventory.active, 0);
+		assert_eq!(inventory.active_item(), Some(ItemId::Sword));
+	}
+}
@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::components;
+use crate::events;
+use crate::inventory;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Update, (spawn_dropped_loot, collect_pickups).in_set(system::GameSet::CommandsAction))
+		.add_systems(Update, (apply_pickups, tick_speed_buffs).in_set(system::GameSet::SingleAction))
+		.add_systems(Update, pickup_magnet.in_set(system::GameSet::ContinousAction))
+		.add_systems(Update, (bob_sprites, coin_counter).in_set(system::GameSet::Apply));
+	}
+}
+
+//How long dropped loot stays on the ground
+const LOOT_LIFETIME      : f32 = 20.0;
+//Dropped loot is scattered around the drop position
+const SCATTER            : f32 = 10.0;
+//Pickups closer than this to the player are pulled towards it
+const MAGNET_RADIUS      : f32 = 60.0;
+const MAGNET_SPEED       : f32 = 200.0;
+const CURRENCY_AMOUNT    : u32 = 5;
+const CURRENCY_TEXTURE   : &str = "textures/rpg/props/generic-rpg-loot05.png";
+
+//Currency goes straight to the wallet, items go to the inventory
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum LootKind {
+	Currency,
+	Item(inventory::ItemId),
+}
+
+impl LootKind {
+	pub fn texture_path(self, registry: &inventory::ItemRegistry) -> Option<&'static str> {
+		match self {
+			LootKind::Currency   => Some(CURRENCY_TEXTURE),
+			LootKind::Item(item) => registry.get(item).map(|def| def.texture),
+		}
+	}
+}
+
+//Weighted table of drops, an entry without a kind is a roll that drops nothing.
+//Tables written in code borrow their entries, tables read from files own them
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct DropTable {
+	pub rolls  : usize,
+	pub entries: Cow<'static, [(f32, Option<LootKind>)]>,
+}
+
+impl DropTable {
+	pub fn roll(&self, rng: &mut impl Rng) -> Vec<LootKind> {
+		let total: f32 = self.entries.iter().map(|(weight, _)| weight).sum();
+		let mut drops = Vec::new();
+		if total <= 0.0 {
+			return drops;
+		}
+
+		for _ in 0..self.rolls {
+			let mut pick = rng.gen_range(0.0..total);
+			for (weight, kind) in self.entries.iter() {
+				if pick < *weight {
+					drops.extend(kind);
+					break;
+				}
+				pick -= weight;
+			}
+		}
+		drops
+	}
+}
+
+//The delay is how long it takes until the pickup can be collected
+pub fn spawn_pickup(
+	commands: &mut Commands,
+	r_asset_server: &AssetServer,
+	registry: &inventory::ItemRegistry,
+	kind: LootKind,
+	position: Vec2,
+	delay: f32,
+) -> Entity {
+	let mut rng = rand::thread_rng();
+	let texture = kind.texture_path(registry).map(|path| r_asset_server.load(path)).unwrap_or_default();
+	commands.spawn((
+		SpatialBundle {
+			transform: Transform::from_translation(position.extend(0.0)),
+			..default()
+		},
+		components::Pickup { kind, delay: Timer::from_seconds(delay, TimerMode::Once) },
+		components::Intersect { size: Vec2::splat(12.0) },
+		components::Velocity::default(),
+		components::DepthSort { base: 900.0 },
+		components::DespawnerTimer::new(bevy::utils::Duration::from_secs_f32(LOOT_LIFETIME)),
+	))
+	.with_children(|parent| {
+		parent.spawn((
+			SpriteBundle {
+				texture,
+				..default()
+			},
+			components::Bob {
+				amplitude: 2.0,
+				speed    : 4.0,
+				phase    : rng.gen_range(0.0..std::f32::consts::TAU),
+			},
+		));
+	}).id()
+}
+
+fn spawn_dropped_loot(
+	mut commands: Commands,
+	r_asset_server: Res<AssetServer>,
+	r_registry: Res<inventory::ItemRegistry>,
+	mut er_loot_drop: EventReader<events::LootDropEvent>,
+) {
+	let mut rng = rand::thread_rng();
+	for event in er_loot_drop.read() {
+		let offset = Vec2::new(rng.gen_range(-SCATTER..SCATTER), rng.gen_range(-SCATTER..SCATTER));
+		spawn_pickup(&mut commands, &r_asset_server, &r_registry, event.kind, event.position + offset, 0.0);
+	}
+}
+
+fn pickup_magnet(
+	mut q_pickup: Query<(&mut components::Velocity, &mut components::Pickup, &Transform)>,
+	q_player: Query<&Transform, With<components::PlayerCharacter>>,
+	r_time: Res<Time>,
+) {
+	let Ok(player_transform) = q_player.get_single() else { return };
+	let target = player_transform.translation.truncate();
+
+	for (mut velocity, mut pickup, transform) in q_pickup.iter_mut() {
+		pickup.delay.tick(r_time.delta());
+		if !pickup.delay.finished() {
+			continue;
+		}
+
+		let offset = target - transform.translation.truncate();
+		let distance = offset.length();
+		if distance > MAGNET_RADIUS || distance == 0.0 {
+			continue;
+		}
+		//the pull gets stronger as the pickup gets closer
+		let strength = 1.0 - distance / MAGNET_RADIUS;
+		velocity.v += offset / distance * MAGNET_SPEED * (0.3 + strength);
+	}
+}
+
+//Items are left on the ground while the inventory has no room for them.
+//Room taken by pickups collected earlier in the frame is counted, they are only added to the inventory later
+fn collect_pickups(
+	mut commands: Commands,
+	r_registry: Res<inventory::ItemRegistry>,
+	mut er_intersect: EventReader<events::IntersectEvent>,
+	mut ew_picked_up: EventWriter<events::PickedUpEvent>,
+	q_player: Query<(Entity, &inventory::Inventory), With<components::PlayerCharacter>>,
+	q_pickup: Query<&components::Pickup>,
+) {
+	let Ok((player_id, inventory)) = q_player.get_single() else { return };
+	let mut collected = HashSet::new();
+	let mut room = inventory.clone();
+
+	for event in er_intersect.read() {
+		let (a, b) = event.ab;
+		if a != player_id || collected.contains(&b) {
+			continue;
+		}
+		let Ok(pickup) = q_pickup.get(b) else { continue };
+		if !pickup.delay.finished() {
+			continue;
+		}
+		if let LootKind::Item(item) = pickup.kind {
+			if !room.add(item, &r_registry) {
+				continue;
+			}
+		}
+
+		collected.insert(b);
+		ew_picked_up.send(events::PickedUpEvent { collector: a, kind: pickup.kind });
+		commands.entity(b).despawn_recursive();
+	}
+}
+
+fn apply_pickups(
+	r_registry: Res<inventory::ItemRegistry>,
+	mut er_picked_up: EventReader<events::PickedUpEvent>,
+	mut q_collector: Query<(&mut components::Wallet, &mut inventory::Inventory)>,
+) {
+	for event in er_picked_up.read() {
+		let Ok((mut wallet, mut inventory)) = q_collector.get_mut(event.collector) else { continue };
+
+		match event.kind {
+			LootKind::Currency   => wallet.coins += CURRENCY_AMOUNT,
+			LootKind::Item(item) => { inventory.add(item, &r_registry); },
+		}
+	}
+}
+
+fn tick_speed_buffs(
+	mut commands: Commands,
+	mut q_buff: Query<(Entity, &mut components::SpeedBuff)>,
+	r_time: Res<Time>,
+) {
+	for (id, mut buff) in q_buff.iter_mut() {
+		buff.timer.tick(r_time.delta());
+		if buff.timer.finished() {
+			commands.entity(id).remove::<components::SpeedBuff>();
+		}
+	}
+}
+
+fn bob_sprites(
+	mut q_bob: Query<(&mut Transform, &components::Bob)>,
+	r_time: Res<Time>,
+) {
+	for (mut transform, bob) in q_bob.iter_mut() {
+		transform.translation.y = f32::sin(r_time.elapsed_seconds() * bob.speed + bob.phase) * bob.amplitude;
+	}
+}
+
+fn coin_counter(
+	mut q_text: Query<(&mut Text, &components::CoinCounter)>,
+	q_wallet: Query<&components::Wallet, Changed<components::Wallet>>,
+) {
+	for (mut text, counter) in q_text.iter_mut() {
+		let Ok(wallet) = q_wallet.get(counter.id) else { continue };
+		text.sections[0].value = format!("Coins: {}", wallet.coins);
+	}
+}
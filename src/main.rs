@@ -15,6 +15,22 @@ mod player;
 mod camera;
 mod map;
 mod boss;
+mod props;
+mod loot;
+mod chest;
+mod save;
+mod inventory;
+mod projectile;
+mod block;
+mod stamina;
+mod actions;
+mod attack_pattern;
+mod telegraph;
+mod enemy;
+mod waves;
+mod pathfinding;
+mod broadphase;
+mod steering;
 
 fn main() {
     App::new()
@@ -27,10 +43,36 @@ fn main() {
 			player::PlayerPlugin,
 			player_weapon::PlayerWeaponPlugin,
 			system::SystemSchedulePlugin,
-			boss::BossPlugin
+			boss::BossPlugin,
+			props::PropsPlugin,
+			loot::LootPlugin,
+			chest::ChestPlugin,
+			save::SavePlugin,
+			inventory::InventoryPlugin,
+			projectile::ProjectilePlugin,
+			block::BlockPlugin,
+			stamina::StaminaPlugin,
+		))
+		.add_plugins((
+			actions::ActionsPlugin,
+			telegraph::TelegraphPlugin,
+			enemy::EnemyPlugin,
+			waves::WavePlugin,
+			pathfinding::PathfindingPlugin,
+			broadphase::BroadphasePlugin,
+			steering::SteeringPlugin,
 		))
 		.add_event::<events::ShakeEvent>()
 		.add_event::<events::IntersectEvent>()
+		.add_event::<events::LootDropEvent>()
+		.add_event::<events::PickedUpEvent>()
+		.add_event::<events::ItemUsedEvent>()
+		.add_event::<events::DamagedEvent>()
+		.add_event::<events::ParryEvent>()
+		.add_event::<events::ExhaustedEvent>()
+		.add_event::<events::BossPhaseEvent>()
+		.add_event::<events::WaveStartedEvent>()
+		.add_event::<events::WaveClearedEvent>()
 		.add_systems(Startup, setup)
 		.add_systems(Update, boss_death.in_set(system::GameSet::CommandsAction))
 		.add_systems(Update, player_death.in_set(system::GameSet::CommandsAction))
@@ -65,7 +107,7 @@ fn boss_death(
 		})
 		.with_children(|commands| {
 			let text_bundle = TextBundle::from_section(
-				format!("You've killed the boss, there is nothing to be done beyound here"),
+				"You've killed the boss, there is nothing to be done beyound here",
 				TextStyle {
 					font_size: 60.0,
 					color: Color::RED,
@@ -107,7 +149,7 @@ fn player_death(
 		})
 		.with_children(|commands| {
 			let text_bundle = TextBundle::from_section(
-				format!("You've died, there is nothing to be done anymore"),
+				"You've died, there is nothing to be done anymore",
 				TextStyle {
 					font_size: 60.0,
 					color: Color::RED,
@@ -140,7 +182,10 @@ fn setup(
 			transform: Transform::from_xyz(0.0, 0.0, 900.0),
 			//components::WalkAnimate::new(25.0, 1.0, 2.0),
 			walk_animate: components::WalkAnimate::new_with_marker(25.0, 1.0, 2.0, BevyDuration::from_secs_f32(0.1)),
-			health: components::Health { current: 100.0, ..default() },
+			health: components::Health::new(100.0),
+			depth_sort: components::DepthSort { base: 900.0 },
+			focus: components::Focus::new(100.0),
+			stamina: components::Stamina::new(100.0, 30.0, 0.6),
 			..default()
 		},
 		components::Intersect{
@@ -170,4 +215,68 @@ fn setup(
 		},
 		components::HealthMeter { id: player_id },
 	));
+
+	commands.spawn((
+		NodeBundle {
+			style: Style {
+				position_type: PositionType::Absolute,
+				left: Val::Px(45.0),
+				width: Val::Px(10.0),
+				height: Val::Px(200.0),
+				..default()
+			},
+			background_color: Color::CYAN.into(),
+			..default()
+		},
+		components::FocusMeter { id: player_id },
+	));
+
+	commands.spawn((
+		NodeBundle {
+			style: Style {
+				position_type: PositionType::Absolute,
+				left: Val::Px(28.0),
+				width: Val::Px(15.0),
+				height: Val::Px(300.0),
+				..default()
+			},
+			background_color: Color::YELLOW.into(),
+			..default()
+		},
+		components::StaminaMeter { id: player_id, flash: Timer::default() },
+	));
+
+	commands.spawn((
+		NodeBundle {
+			style: Style {
+				position_type: PositionType::Absolute,
+				left: Val::Px(10.0),
+				bottom: Val::Px(85.0),
+				width: Val::Px(60.0),
+				height: Val::Px(8.0),
+				..default()
+			},
+			background_color: Color::WHITE.into(),
+			..default()
+		},
+		components::DashMeter { id: player_id },
+	));
+
+	commands.spawn((
+		TextBundle::from_section(
+			"Coins: 0",
+			TextStyle {
+				font_size: 30.0,
+				color: Color::GOLD,
+				..default()
+			},
+		)
+		.with_style(Style {
+			position_type: PositionType::Absolute,
+			left: Val::Px(10.0),
+			bottom: Val::Px(10.0),
+			..default()
+		}),
+		components::CoinCounter { id: player_id },
+	));
 }
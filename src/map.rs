@@ -7,6 +7,7 @@ pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
 	fn build(&self, app: &mut App) {
+		app.init_resource::<TileMetadata>();
 		app.add_systems(Startup, generate_map);
 		app.add_plugins(SimpleTileMapPlugin);
 	}
@@ -18,11 +19,163 @@ const MAP_SIZE:        isize = 200;
 //max is[SPRITE_DIVISION * SPRITE_DIVISION - 1], the amount of blocks in the sprite that can be used in tiles
 //how many are used will affect the look of the map
 const BLOCK_RANGE:     usize = 50;
+//the tilemap is scaled up, so this is the size of a tile in world units
+pub const TILE_WORLD_SIZE: f32   = 3.0;
+//patches of other surfaces are kept out of this radius(in tiles) so the spawn area is always plain grass
+const CLEAR_RADIUS:    f32   = 40.0;
+//patches are only generated inside the playable area, see BOUNDS in miscellaneous
+const PATCH_EXTENT:    i32   = 110;
 
-fn generate_map(
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum SurfaceKind {
+	#[default]
+	Grass,
+	Water,
+	Mud,
+	Lava,
+	Rock,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TileProperties {
+	pub walkable         : bool,
+	pub speed_multiplier : f32,
+	pub surface          : SurfaceKind,
+	pub damage_per_second: f32,
+}
+
+//Property table of every surface kind
+impl SurfaceKind {
+	pub fn properties(self) -> TileProperties {
+		let (walkable, speed_multiplier, damage_per_second) = match self {
+			SurfaceKind::Grass => (true,  1.0, 0.0),
+			SurfaceKind::Water => (true,  0.5, 0.0),
+			SurfaceKind::Mud   => (true,  0.7, 0.0),
+			SurfaceKind::Lava  => (true,  0.8, 10.0),
+			SurfaceKind::Rock  => (false, 0.0, 0.0),
+		};
+		TileProperties { walkable, speed_multiplier, surface: self, damage_per_second }
+	}
+
+	fn tint(self) -> Color {
+		match self {
+			SurfaceKind::Grass => Color::WHITE,
+			SurfaceKind::Water => Color::rgb(0.3, 0.5, 1.0),
+			SurfaceKind::Mud   => Color::rgb(0.6, 0.45, 0.3),
+			SurfaceKind::Lava  => Color::rgb(1.0, 0.35, 0.1),
+			SurfaceKind::Rock  => Color::rgb(0.4, 0.4, 0.4),
+		}
+	}
+}
+
+//Surface of every tile generated by generate_map, used to look up tile properties by world position
+//Tiles can also be blocked by things standing on them, like props
+#[derive(Clone, Default, Resource)]
+pub struct TileMetadata {
+	surfaces: Vec<SurfaceKind>,
+	blocked : Vec<bool>,
+}
+
+impl TileMetadata {
+	//A whole map of one surface with nothing blocked
+	#[cfg(test)]
+	pub fn filled(surface: SurfaceKind) -> Self {
+		let tiles = (MAP_SIZE * MAP_SIZE * 4) as usize;
+		Self { surfaces: vec![surface; tiles], blocked: vec![false; tiles] }
+	}
+
+	pub fn world_to_tile(position: Vec2) -> IVec2 {
+		(position / TILE_WORLD_SIZE).round().as_ivec2()
+	}
+
+	pub fn tile_to_world(tile: IVec2) -> Vec2 {
+		tile.as_vec2() * TILE_WORLD_SIZE
+	}
+
+	fn index(tile: IVec2) -> Option<usize> {
+		let size = MAP_SIZE as i32;
+		if tile.x < -size || tile.x >= size || tile.y < -size || tile.y >= size {
+			return None;
+		}
+		Some(((tile.x + size) * size * 2 + (tile.y + size)) as usize)
+	}
+
+	pub fn surface_at_tile(&self, tile: IVec2) -> Option<SurfaceKind> {
+		Self::index(tile).and_then(|i| self.surfaces.get(i).copied())
+	}
+
+	//Outside of the map(or before it is generated) nothing can be looked up
+	pub fn properties_at(&self, position: Vec2) -> Option<TileProperties> {
+		let tile = Self::world_to_tile(position);
+		let mut properties = self.surface_at_tile(tile).map(SurfaceKind::properties)?;
+		if Self::index(tile).is_some_and(|i| self.blocked.get(i).copied().unwrap_or(false)) {
+			properties.walkable = false;
+		}
+		Some(properties)
+	}
+
+	//Marks every tile overlapped by the area as blocked or unblocked
+	pub fn set_area_blocked(&mut self, center: Vec2, size: Vec2, blocked: bool) {
+		let min = Self::world_to_tile(center - size / 2.0);
+		let max = Self::world_to_tile(center + size / 2.0);
+		for x in min.x..=max.x {
+			for y in min.y..=max.y {
+				let Some(i) = Self::index(IVec2::new(x, y)) else { continue };
+				if let Some(tile) = self.blocked.get_mut(i) {
+					*tile = blocked;
+				}
+			}
+		}
+	}
+
+	pub fn is_walkable(&self, position: Vec2) -> bool {
+		self.properties_at(position).map_or(true, |properties| properties.walkable)
+	}
+
+	pub fn is_tile_walkable(&self, tile: IVec2) -> bool {
+		self.is_walkable(Self::tile_to_world(tile))
+	}
+}
+
+struct SurfacePatch {
+	surface: SurfaceKind,
+	center : Vec2,
+	radius : f32,
+}
+
+//Scatters a few round patches of each surface kind, where patches overlap the first one wins
+fn generate_patches(rng: &mut ThreadRng) -> Vec<SurfacePatch> {
+	let rules = [
+		(SurfaceKind::Water, 6, 6.0..14.0),
+		(SurfaceKind::Mud,   6, 5.0..10.0),
+		(SurfaceKind::Lava,  4, 3.0..7.0),
+		(SurfaceKind::Rock,  5, 2.0..5.0),
+	];
+
+	let mut patches = Vec::new();
+	for (surface, count, radius) in rules {
+		let mut placed = 0;
+		while placed < count {
+			let center = Vec2::new(
+				rng.gen_range(-PATCH_EXTENT..PATCH_EXTENT) as f32,
+				rng.gen_range(-PATCH_EXTENT..PATCH_EXTENT) as f32,
+			);
+			let radius = rng.gen_range(radius.clone());
+			if center.length() - radius < CLEAR_RADIUS {
+				continue;
+			}
+			patches.push(SurfacePatch { surface, center, radius });
+			placed += 1;
+		}
+	}
+	patches
+}
+
+pub fn generate_map(
 	mut commands: Commands,
 	r_asset_server: Res<AssetServer>,
 	mut rm_texture_atlases: ResMut<Assets<TextureAtlas>>,
+	mut rm_tile_metadata: ResMut<TileMetadata>,
 ) {
 	let texture_handle = r_asset_server.load("textures/rpg/tiles/generic-rpg-tile02.png");
     let texture_atlas =
@@ -36,23 +189,33 @@ fn generate_map(
 
 	let mut rng = rand::thread_rng();
 	let mut tiles: Vec<(IVec3, Option<Tile>)> = Vec::new();
+	let patches = generate_patches(&mut rng);
+	let mut surfaces = Vec::with_capacity((MAP_SIZE * MAP_SIZE * 4) as usize);
 
 	for x in -MAP_SIZE..MAP_SIZE {
 		for y in -MAP_SIZE..MAP_SIZE {
+			let position = Vec2::new(x as f32, y as f32);
+			let surface = patches.iter()
+				.find(|patch| patch.center.distance(position) <= patch.radius)
+				.map_or(SurfaceKind::Grass, |patch| patch.surface);
+			surfaces.push(surface);
+
 			let index = rng.gen_range(0..BLOCK_RANGE);
 			tiles.push((
 				IVec3::new(x as i32, y as i32, 0),
-				Some(Tile { sprite_index: index as u32, color: Color::WHITE, ..default() })
+				Some(Tile { sprite_index: index as u32, color: surface.tint(), ..default() })
 				));
 		}
 	}
+	rm_tile_metadata.blocked = vec![false; surfaces.len()];
+	rm_tile_metadata.surfaces = surfaces;
 
 	let mut tilemap = TileMap::default();
 	tilemap.set_tiles(tiles);
 
 	commands.spawn(
 		TileMapBundle {
-			transform: Transform::from_scale(Vec3::splat(3.0)),
+			transform: Transform::from_scale(Vec3::splat(TILE_WORLD_SIZE)),
 			tilemap,
 			texture_atlas: texture_atlas_handle.clone(),
 			..default()
@@ -1,30 +1,40 @@
 use bevy::prelude::*;
+use bevy::asset::io::file::FileAssetReader;
 use bevy::sprite::collide_aabb;
 use bevy_particle_systems::*;
 
 use crate::system;
+use crate::broadphase;
 use crate::components;
 use crate::events;
 use crate::bundles;
+use crate::map;
+use crate::block;
 
 use bevy::utils::Duration as BevyDuration;
+use std::path::PathBuf;
 
+//How fast knockback fades, higher values stop sooner
+pub const KNOCKBACK_DECAY: f32 = 10.0;
+//Seconds between two hits from the surface being stood on
+const SURFACE_DAMAGE_TICK: f32 = 0.5;
 
 #[derive(Clone, Default)]
 pub struct MiscellaneousPlugin;
 
 impl Plugin for MiscellaneousPlugin {
 	fn build(&self, app: &mut App) {
-		app
+		app.insert_resource(SurfaceDamageTick(Timer::from_seconds(SURFACE_DAMAGE_TICK, TimerMode::Repeating)))
 		//why does it need to be empty to execute it?
 		//events are not consistantly cleared in each frame, if checking is executed each frame it will
 		//inconsistantly stack intersection events
 		//it is possibly to control the clearing of events but using run_if is fine considering the scope of this project
-		.add_systems(Update, check_intersect.run_if(intersect_empty()).in_set(system::GameSet::Check))
-		.add_systems(Update, (do_walk_animation, move_with_velocity).chain().in_set(system::GameSet::Apply))
+		.add_systems(Update, check_intersect.run_if(intersect_empty()).after(broadphase::rebuild_spatial_hash).in_set(system::GameSet::Check))
+		.add_systems(Update, (do_walk_animation, move_with_velocity, depth_sort).chain().in_set(system::GameSet::Apply))
 		.add_systems(Update, despawn_by_timer.in_set(system::GameSet::Despawn))
 		.add_systems(Update, health_meter.in_set(system::GameSet::CommandsAction))
-		.add_systems(Update, (damage_zone_apply, damage_apply).in_set(system::GameSet::Apply));
+		.add_systems(Update, knockback_apply.in_set(system::GameSet::ContinousAction))
+		.add_systems(Update, (surface_damage.before(damage_apply), damage_zone_apply, damage_apply).in_set(system::GameSet::Apply));
 	}
 }
 
@@ -33,12 +43,43 @@ fn intersect_empty() -> impl Fn(EventReader<events::IntersectEvent>) -> bool + C
 	move |reader: EventReader<events::IntersectEvent>| { reader.is_empty() }
 }
 
+#[derive(Clone, Resource)]
+struct SurfaceDamageTick(Timer);
+
+//Damage from the surface walked on, dealt in ticks so it doesn't bleed every frame
+//Enemies and the boss burn like the player, breakable props standing still don't
+fn surface_damage(
+	mut q_walker: Query<(&Transform, &mut components::Health, Has<components::Invulnerable>), With<components::Velocity>>,
+	r_tile_metadata: Res<map::TileMetadata>,
+	r_time: Res<Time>,
+	mut rm_tick: ResMut<SurfaceDamageTick>,
+) {
+	if !rm_tick.0.tick(r_time.delta()).just_finished() {
+		return;
+	}
+
+	for (transform, mut health, invulnerable) in q_walker.iter_mut() {
+		if invulnerable {
+			continue;
+		}
+		let Some(properties) = r_tile_metadata.properties_at(transform.translation.truncate()) else { continue };
+		health.unapplied_damage += properties.damage_per_second * SURFACE_DAMAGE_TICK;
+	}
+}
+
 //Will apply the unapplied_damage and create blood particles
+//Invulnerable entities take no damage at all
 fn damage_apply(
 	mut commands: Commands,
-	mut q_health: Query<(&Transform, &mut components::Health)>
+	mut q_health: Query<(Entity, &Transform, &mut components::Health, Has<components::Invulnerable>)>,
+	mut ew_damaged: EventWriter<events::DamagedEvent>,
 ) {
-	for (transform, mut health) in q_health.iter_mut() {
+	for (id, transform, mut health, invulnerable) in q_health.iter_mut() {
+		if invulnerable {
+			health.unapplied_damage = 0.0;
+			continue;
+		}
+
 		if health.unapplied_damage == 0.0 {
 			continue;
 		}
@@ -52,6 +93,7 @@ fn damage_apply(
 			(Color::RED..Color::rgba(1.0, 0.0, 0.0, 0.0)).into()
 		);
 		health.current -= health.unapplied_damage;
+		ew_damaged.send(events::DamagedEvent { target: id });
 
 		health.unapplied_damage = 0.0;
 	}
@@ -119,34 +161,65 @@ fn do_walk_animation(
 	}
 }
 
+fn knockback_apply(
+	mut commands: Commands,
+	mut q_knockback: Query<(Entity, &mut components::Knockback, Option<&mut components::Velocity>)>,
+	r_time: Res<Time>,
+) {
+	for (id, mut knockback, velocity) in q_knockback.iter_mut() {
+		let Some(mut velocity) = velocity else {
+			commands.entity(id).remove::<components::Knockback>();
+			continue;
+		};
+		velocity.v += knockback.v;
+
+		let decay = knockback.decay;
+		knockback.v *= f32::exp(-decay * r_time.delta_seconds());
+		if knockback.v.length() < 1.0 {
+			commands.entity(id).remove::<components::Knockback>();
+		}
+	}
+}
+
+//Staggered zones deal no damage, blocking targets can reduce or parry it
+//Hostile zones don't hurt hostile targets
 fn damage_zone_apply(
 	mut er_intersect: EventReader<events::IntersectEvent>,
-	mut q_health    : Query<(Entity, &mut components::Health), With<components::Intersect>>,
-	q_zone          : Query<(Entity, &components::DamageZone), With<components::Intersect>>,
+	mut q_health    : Query<(&mut components::Health, &Transform, Option<&components::Blocking>), With<components::Intersect>>,
+	q_zone          : Query<(&components::DamageZone, &Transform), Without<components::Stagger>>,
+	q_hostile       : Query<(), With<components::Hostile>>,
+	mut ew_parry    : EventWriter<events::ParryEvent>,
 ) {
 	for event in er_intersect.read() {
 		let (a, b) = event.ab;
-		let health_result = q_health.get_component_mut::<components::Health>(a);
-		let zone_result   = q_zone.get_component::<components::DamageZone>(b);
-		let (Ok(mut health), Ok(zone)) = (health_result, zone_result) else { return };
-		health.unapplied_damage += zone.damage;
+		let (Ok((mut health, transform, blocking)), Ok((zone, zone_transform))) = (q_health.get_mut(a), q_zone.get(b)) else { continue };
+		if zone.ignore == Some(a) || (q_hostile.contains(a) && q_hostile.contains(b)) {
+			continue;
+		}
+		let position = transform.translation.truncate();
+		match block::block_outcome(blocking, position, zone_transform.translation.truncate()) {
+			block::BlockOutcome::Parried => ew_parry.send(events::ParryEvent { attacker: Some(b), position }),
+			block::BlockOutcome::Blocked(multiplier) => health.unapplied_damage += zone.damage * multiplier,
+			block::BlockOutcome::Unblocked => health.unapplied_damage += zone.damage,
+		}
 	}
 }
 
+//Only pairs sharing a cell of the spatial hash are compared
 fn check_intersect(
 	mut ew_intersect: EventWriter<events::IntersectEvent>,
-	q_intersect: Query<(Entity, &components::Intersect, &Transform)>,
+	r_spatial_hash: Res<broadphase::SpatialHash>,
 ) {
-	for (id_a, intersect_a, transform_a) in q_intersect.iter() {
-		for (id_b, intersect_b, transform_b) in q_intersect.iter() {
-			if id_a == id_b {
+	for a in r_spatial_hash.entries() {
+		for b in r_spatial_hash.query(a.position, a.size) {
+			if a.id == b.id {
 				continue;
 			}
-			let collide = collide_aabb::collide(transform_a.translation, intersect_a.size,
-				transform_b.translation, intersect_b.size);
+			let collide = collide_aabb::collide(a.position.extend(0.0), a.size,
+				b.position.extend(0.0), b.size);
 
 			if let Some(_result) = collide {
-				ew_intersect.send(events::IntersectEvent{ab: (id_a, id_b)});
+				ew_intersect.send(events::IntersectEvent{ab: (a.id, b.id)});
 			}
 		}
 	}
@@ -155,16 +228,43 @@ fn check_intersect(
 const BOUNDS: f32 = 170.0;
 
 //Each entity transform is moved according to it's velocity value and the value is reset
+//Entities with health are slowed by the surface they stand on and can't walk into blocking tiles
+//Multiplier of the speed of anything walking at the position
+pub fn surface_speed(r_tile_metadata: &map::TileMetadata, position: Vec2) -> f32 {
+	r_tile_metadata.properties_at(position).map_or(1.0, |properties| properties.speed_multiplier.max(0.1))
+}
+
 fn move_with_velocity(
-	mut query: Query<(&mut Transform, &mut components::Velocity)>,
+	mut query: Query<(&mut Transform, &mut components::Velocity, Has<components::Health>, Has<components::Dashing>)>,
+	r_tile_metadata: Res<map::TileMetadata>,
 	r_time: Res<Time>,
 ) {
 	//Using delta seconds here is not optimal but is fine
 	let dt = r_time.delta_seconds();
-	for (mut transform, mut vel) in query.iter_mut() {
+	for (mut transform, mut vel, walks, dashing) in query.iter_mut() {
 		let mut translation = transform.translation;
 		let z_order = translation.z;
-		translation += Vec3::new(vel.v.x * dt, vel.v.y * dt, 0.0);
+		let mut step = vel.v * dt;
+
+		if walks {
+			let position = translation.truncate();
+			//dashes cover the same distance whatever they cross
+			if !dashing {
+				step *= surface_speed(&r_tile_metadata, position);
+			}
+
+			//try sliding along each axis when the full step is blocked
+			if !r_tile_metadata.is_walkable(position + step) {
+				step = if r_tile_metadata.is_walkable(position + Vec2::new(step.x, 0.0)) {
+					Vec2::new(step.x, 0.0)
+				} else if r_tile_metadata.is_walkable(position + Vec2::new(0.0, step.y)) {
+					Vec2::new(0.0, step.y)
+				} else {
+					Vec2::ZERO
+				};
+			}
+		}
+		translation += step.extend(0.0);
 
 		//bounds
 		let extents = Vec3::from((Vec2::splat(BOUNDS * 2.0), 0.0));
@@ -176,6 +276,21 @@ fn move_with_velocity(
 	}
 }
 
+//How much the z order changes for each world unit in y
+const DEPTH_PER_UNIT: f32 = 0.01;
+
+fn depth_sort(
+	mut query: Query<(&mut Transform, &components::DepthSort), Changed<Transform>>,
+) {
+	for (mut transform, depth) in query.iter_mut() {
+		let z = depth.base - transform.translation.y * DEPTH_PER_UNIT;
+		//avoids triggering change detection every frame
+		if transform.translation.z != z {
+			transform.translation.z = z;
+		}
+	}
+}
+
 fn despawn_by_timer(
 	mut commands: Commands,
 	mut query: Query<(Entity, &mut components::DespawnerTimer)>,
@@ -185,11 +300,40 @@ fn despawn_by_timer(
 		timer.timer.tick(r_time.delta());
 
 		if timer.timer.finished() {
-			commands.entity(id).despawn();
+			commands.entity(id).despawn_recursive();
 		}
 	}
 }
 
+//Big text at the top of the screen that goes away after a while
+//Data files are read straight from the disk, out of the same folder the asset server loads from
+pub fn asset_folder(app: &App) -> PathBuf {
+	let assets = app.get_added_plugins::<AssetPlugin>()
+		.first()
+		.map_or_else(|| AssetPlugin::default().file_path, |plugin| plugin.file_path.clone());
+	FileAssetReader::get_base_path().join(assets)
+}
+
+pub fn spawn_banner(commands: &mut Commands, text: String, color: Color, seconds: f32) -> Entity {
+	commands.spawn((
+		TextBundle::from_section(
+			text,
+			TextStyle {
+				font_size: 50.0,
+				color,
+				..default()
+			},
+		)
+		.with_style(Style {
+			position_type: PositionType::Absolute,
+			top: Val::Px(20.0),
+			justify_self: JustifySelf::Center,
+			..default()
+		}),
+		components::DespawnerTimer::new(BevyDuration::from_secs_f32(seconds)),
+	)).id()
+}
+
 pub fn generic_particle_burst(
 	commands: &mut Commands,
 	translation: Vec3,
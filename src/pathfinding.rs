@@ -0,0 +1,333 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::components;
+use crate::map::{self, TileMetadata};
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct PathfindingPlugin;
+
+impl Plugin for PathfindingPlugin {
+	fn build(&self, app: &mut App) {
+		app.init_resource::<FlowField>()
+		.add_systems(Update, (update_flow_field, follow_paths).in_set(system::GameSet::SingleAction));
+	}
+}
+
+//Searches never go further than this from where they start, in tiles
+const SEARCH_RADIUS  : i32 = 120;
+//Searches give up after looking at this many tiles, an unreachable goal would otherwise flood the whole radius
+const MAX_EXPANDED   : usize = 4000;
+//The flow field covers this many tiles around the player in every direction
+const FLOW_RADIUS    : i32 = 120;
+//How many tiles the player has to move before the flow field is built again
+const FLOW_REFRESH   : i32 = 4;
+//A path is searched again once the player is this far from where it leads
+const REPATH_DISTANCE: f32 = 24.0;
+const REPATH_INTERVAL: f32 = 0.25;
+//Waited instead after a search found nothing, the player has to move somewhere reachable first anyway
+const REPATH_BACKOFF : f32 = 1.0;
+//Waypoints closer than this count as reached
+const WAYPOINT_RADIUS: f32 = 4.0;
+//Costs of a straight and a diagonal step, about 1 and the square root of 2
+const STRAIGHT_COST  : u32 = 10;
+const DIAGONAL_COST  : u32 = 14;
+
+const NEIGHBOURS: [IVec2; 8] = [
+	IVec2::new(1, 0),
+	IVec2::new(-1, 0),
+	IVec2::new(0, 1),
+	IVec2::new(0, -1),
+	IVec2::new(1, 1),
+	IVec2::new(1, -1),
+	IVec2::new(-1, 1),
+	IVec2::new(-1, -1),
+];
+
+//Diagonal steps can't squeeze between two blocked tiles
+fn can_step(metadata: &TileMetadata, from: IVec2, step: IVec2) -> bool {
+	metadata.is_tile_walkable(from + step)
+		&& (step.x == 0 || step.y == 0 || (
+			metadata.is_tile_walkable(from + IVec2::new(step.x, 0))
+			&& metadata.is_tile_walkable(from + IVec2::new(0, step.y))
+		))
+}
+
+//Octile distance, never more than what the cheapest path costs
+fn heuristic(from: IVec2, to: IVec2) -> u32 {
+	let delta = (to - from).abs();
+	let (short, long) = (delta.min_element() as u32, delta.max_element() as u32);
+	DIAGONAL_COST * short + STRAIGHT_COST * (long - short)
+}
+
+//Samples the segment every half tile, so no tile it crosses is missed
+pub fn line_of_sight(metadata: &TileMetadata, from: Vec2, to: Vec2) -> bool {
+	let steps = (from.distance(to) / (map::TILE_WORLD_SIZE / 2.0)).ceil() as usize;
+	(1..=steps).all(|i| metadata.is_walkable(from.lerp(to, i as f32 / steps as f32)))
+}
+
+//Drops every waypoint that can be skipped by walking straight to a later one
+fn smooth_path(metadata: &TileMetadata, start: Vec2, points: Vec<Vec2>) -> Vec<Vec2> {
+	let mut smoothed = Vec::new();
+	let mut from = start;
+	let mut i = 0;
+	while i < points.len() {
+		let mut furthest = i;
+		while furthest + 1 < points.len() && line_of_sight(metadata, from, points[furthest + 1]) {
+			furthest += 1;
+		}
+		from = points[furthest];
+		smoothed.push(from);
+		i = furthest + 1;
+	}
+	smoothed
+}
+
+//A* over the tile grid, the path leads from `start`(not included) to exactly `goal`
+pub fn find_path(metadata: &TileMetadata, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+	let start_tile = TileMetadata::world_to_tile(start);
+	let goal_tile = TileMetadata::world_to_tile(goal);
+	if !metadata.is_tile_walkable(goal_tile) {
+		return None;
+	}
+
+	let mut open = BinaryHeap::new();
+	let mut came_from: HashMap<IVec2, IVec2> = HashMap::new();
+	let mut costs: HashMap<IVec2, u32> = HashMap::new();
+	costs.insert(start_tile, 0);
+	//IVec2 has no ordering, so tiles go in the heap as tuples
+	open.push(Reverse((heuristic(start_tile, goal_tile), start_tile.x, start_tile.y)));
+
+	let mut expanded = 0;
+	while let Some(Reverse((estimate, x, y))) = open.pop() {
+		let tile = IVec2::new(x, y);
+		//a cheaper way to this tile was pushed after this one
+		if estimate > costs[&tile] + heuristic(tile, goal_tile) {
+			continue;
+		}
+		expanded += 1;
+		if expanded > MAX_EXPANDED {
+			return None;
+		}
+		if tile == goal_tile {
+			let mut tiles = vec![tile];
+			while let Some(previous) = came_from.get(tiles.last().unwrap()) {
+				tiles.push(*previous);
+			}
+			tiles.pop();
+			tiles.reverse();
+
+			let mut points: Vec<Vec2> = tiles.into_iter().map(TileMetadata::tile_to_world).collect();
+			if let Some(last) = points.last_mut() {
+				*last = goal;
+			}
+			return Some(smooth_path(metadata, start, points));
+		}
+
+		let cost = costs[&tile];
+		for step in NEIGHBOURS {
+			let next = tile + step;
+			if (next - start_tile).abs().max_element() > SEARCH_RADIUS || !can_step(metadata, tile, step) {
+				continue;
+			}
+			let next_cost = cost + if step.x == 0 || step.y == 0 { STRAIGHT_COST } else { DIAGONAL_COST };
+			if costs.get(&next).is_some_and(|known| *known <= next_cost) {
+				continue;
+			}
+			costs.insert(next, next_cost);
+			came_from.insert(next, tile);
+			open.push(Reverse((next_cost + heuristic(next, goal_tile), next.x, next.y)));
+		}
+	}
+	None
+}
+
+//How many steps every tile around the player is from it, shared by everything chasing the player
+#[derive(Clone, Default, Resource)]
+pub struct FlowField {
+	target: IVec2,
+	costs : Vec<u32>,
+}
+
+impl FlowField {
+	fn index(&self, tile: IVec2) -> Option<usize> {
+		let local = tile - self.target + IVec2::splat(FLOW_RADIUS);
+		let size = FLOW_RADIUS * 2 + 1;
+		if local.x < 0 || local.y < 0 || local.x >= size || local.y >= size {
+			return None;
+		}
+		Some((local.x * size + local.y) as usize)
+	}
+
+	fn cost(&self, tile: IVec2) -> Option<u32> {
+		self.index(tile).and_then(|i| self.costs.get(i).copied()).filter(|cost| *cost != u32::MAX)
+	}
+
+	//Breadth first from the player, through walkable tiles only
+	fn build(&mut self, metadata: &TileMetadata, target: IVec2) {
+		let size = FLOW_RADIUS * 2 + 1;
+		self.target = target;
+		self.costs = vec![u32::MAX; (size * size) as usize];
+
+		let mut queue = VecDeque::new();
+		if let Some(i) = self.index(target) {
+			self.costs[i] = 0;
+			queue.push_back(target);
+		}
+		while let Some(tile) = queue.pop_front() {
+			let cost = self.costs[self.index(tile).unwrap()];
+			for step in &NEIGHBOURS[..4] {
+				let next = tile + *step;
+				let Some(i) = self.index(next) else { continue };
+				if self.costs[i] != u32::MAX || !metadata.is_tile_walkable(next) {
+					continue;
+				}
+				self.costs[i] = cost + 1;
+				queue.push_back(next);
+			}
+		}
+	}
+
+	//Straight at the target when nothing is in the way, downhill on the field otherwise.
+	//Outside of the field, or where the target can't be reached, it falls back to straight
+	pub fn direction(&self, metadata: &TileMetadata, position: Vec2, target: Vec2) -> Vec2 {
+		let straight = (target - position).normalize_or_zero();
+		if line_of_sight(metadata, position, target) {
+			return straight;
+		}
+
+		let tile = TileMetadata::world_to_tile(position);
+		let Some(current) = self.cost(tile) else { return straight };
+		NEIGHBOURS.iter()
+			.filter(|step| can_step(metadata, tile, **step))
+			.filter_map(|step| self.cost(tile + *step).map(|cost| (cost, *step)))
+			.filter(|(cost, _)| *cost < current)
+			.min_by_key(|(cost, _)| *cost)
+			.map_or(straight, |(_, step)| step.as_vec2().normalize())
+	}
+}
+
+fn update_flow_field(
+	mut rm_flow_field: ResMut<FlowField>,
+	r_tile_metadata: Res<TileMetadata>,
+	q_player_transform: Query<&Transform, With<components::PlayerCharacter>>,
+	q_enemy: Query<(), With<components::Enemy>>,
+) {
+	let Ok(target_transform) = q_player_transform.get_single() else { return };
+	if q_enemy.is_empty() {
+		return;
+	}
+
+	let target = TileMetadata::world_to_tile(target_transform.translation.truncate());
+	let moved = (target - rm_flow_field.target).abs().max_element() >= FLOW_REFRESH;
+	if moved || rm_flow_field.costs.is_empty() || r_tile_metadata.is_changed() {
+		rm_flow_field.build(&r_tile_metadata, target);
+	}
+}
+
+//Paths are searched again when the player walks away from where they lead, a clear line to the player needs none
+fn follow_paths(
+	mut q_follower: Query<(&Transform, &mut components::PathFollower)>,
+	q_player_transform: Query<&Transform, (With<components::PlayerCharacter>, Without<components::PathFollower>)>,
+	r_tile_metadata: Res<TileMetadata>,
+	r_time: Res<Time>,
+) {
+	let Ok(target_transform) = q_player_transform.get_single() else { return };
+	let target = target_transform.translation.truncate();
+
+	for (transform, mut follower) in q_follower.iter_mut() {
+		let position = transform.translation.truncate();
+		follower.cooldown.tick(r_time.delta());
+		while follower.waypoints.last().is_some_and(|waypoint| waypoint.distance(position) <= WAYPOINT_RADIUS) {
+			follower.waypoints.pop();
+		}
+
+		if line_of_sight(&r_tile_metadata, position, target) {
+			follower.waypoints.clear();
+			follower.target = target;
+			continue;
+		}
+		let stale = follower.waypoints.is_empty() || follower.target.distance(target) > REPATH_DISTANCE;
+		if !stale || !follower.cooldown.finished() {
+			continue;
+		}
+
+		let path = find_path(&r_tile_metadata, position, target);
+		let interval = if path.is_some() { REPATH_INTERVAL } else { REPATH_BACKOFF };
+		follower.cooldown = Timer::from_seconds(interval, TimerMode::Once);
+		follower.target = target;
+		follower.waypoints = path.unwrap_or_default();
+		follower.waypoints.reverse();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::map::SurfaceKind;
+
+	fn tile(x: i32, y: i32) -> Vec2 {
+		TileMetadata::tile_to_world(IVec2::new(x, y))
+	}
+
+	//Grass everywhere but a wall 21 tiles high at x = 10
+	fn walled() -> TileMetadata {
+		let mut metadata = TileMetadata::filled(SurfaceKind::Grass);
+		metadata.set_area_blocked(tile(10, 0), Vec2::new(0.0, 20.0 * map::TILE_WORLD_SIZE), true);
+		metadata
+	}
+
+	#[test]
+	fn path_goes_around_a_wall() {
+		let metadata = walled();
+		let (start, goal) = (tile(0, 0), tile(20, 0));
+		assert!(!line_of_sight(&metadata, start, goal));
+
+		let path = find_path(&metadata, start, goal).expect("the wall can be walked around");
+		assert_eq!(path.last(), Some(&goal));
+		let mut from = start;
+		for waypoint in &path {
+			assert!(line_of_sight(&metadata, from, *waypoint), "{from} to {waypoint} crosses the wall");
+			from = *waypoint;
+		}
+		assert!(path.iter().any(|waypoint| waypoint.y.abs() > tile(0, 10).y));
+	}
+
+	#[test]
+	fn no_path_to_an_unreachable_goal() {
+		let mut metadata = TileMetadata::filled(SurfaceKind::Grass);
+		//a goal on a blocked tile, and one walled in on every side
+		metadata.set_area_blocked(tile(5, 5), Vec2::ZERO, true);
+		assert_eq!(find_path(&metadata, tile(0, 0), tile(5, 5)), None);
+
+		for (center, size) in [
+			(tile(20, 3), Vec2::new(6.0, 0.0)),
+			(tile(20, -3), Vec2::new(6.0, 0.0)),
+			(tile(17, 0), Vec2::new(0.0, 6.0)),
+			(tile(23, 0), Vec2::new(0.0, 6.0)),
+		] {
+			metadata.set_area_blocked(center, size * map::TILE_WORLD_SIZE, true);
+		}
+		assert!(metadata.is_walkable(tile(20, 0)));
+		assert_eq!(find_path(&metadata, tile(0, 0), tile(20, 0)), None);
+	}
+
+	#[test]
+	fn flow_field_points_downhill() {
+		let metadata = walled();
+		let mut field = FlowField::default();
+		field.build(&metadata, IVec2::new(20, 0));
+
+		for y in -8..=8 {
+			let position = tile(5, y);
+			let direction = field.direction(&metadata, position, tile(20, 0));
+			let here = field.cost(IVec2::new(5, y)).unwrap();
+			let next = field.cost(IVec2::new(5, y) + direction.round().as_ivec2()).unwrap();
+			assert!(next < here, "from {position} the field leads {direction}, from {here} to {next}");
+		}
+	}
+}
@@ -1,74 +1,130 @@
-use bevy::{
-	prelude::*, input::common_conditions::*
-};
+use bevy::prelude::*;
+
+use bevy::utils::Duration as BevyDuration;
 
 use crate::system;
 use crate::components;
+use crate::events;
+use crate::actions;
 
 #[derive(Clone, Default)]
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
 	fn build(&self, app: &mut App) {
-		app.add_systems(Update, player_char_controls.in_set(system::GameSet::ContinousAction))
-		.add_systems(Update, player_dash_ability.run_if(input_just_pressed(KeyCode::F)).in_set(system::GameSet::SingleAction));
+		app.add_systems(Update, (player_char_controls, dash_movement, dash_cooldown).in_set(system::GameSet::ContinousAction))
+		.add_systems(Update, player_dash_ability.run_if(actions::action_just_pressed(actions::Action::Dash)).in_set(system::GameSet::CommandsAction))
+		.add_systems(Update, dash_meter.in_set(system::GameSet::Apply));
 	}
 }
 
+const DASH_DISTANCE      : f32 = 90.0;
+const DASH_DURATION      : f32 = 0.15;
+const DASH_COOLDOWN      : f32 = 0.8;
+//Seconds between ghost sprites left behind while dashing
+const DASH_GHOST_INTERVAL: f32 = 0.015;
+const DASH_METER_WIDTH   : f32 = 60.0;
+const DASH_STAMINA       : f32 = 25.0;
+
+//Dashes in the held direction, or towards the aim when nothing is held
 fn player_dash_ability(
-	r_inputs: Res<Input<KeyCode>>,
-	mut query: Query<&mut components::Velocity, With<components::PlayerCharacter>>,
+	mut commands: Commands,
+	r_actions: Res<actions::ActionState>,
+	mut query: Query<(Entity, &mut components::DashAbility, &mut components::WalkAnimate, &mut components::Stamina), Without<components::Dashing>>,
+	q_weapon: Query<&components::PlayerWeapon>,
+	mut ew_exhausted: EventWriter<events::ExhaustedEvent>,
 ) {
-	//this check is made with run critirea
-	//if !inputs.just_pressed(KeyCode::F) {
-	//	return
-	//}
-
-	let Ok(mut velocity) = query.get_single_mut() else { return };
-	let mut impulse = Vec2::ZERO;
-	let impulse_amount = 10.0 * 1000.0;
-
-	if r_inputs.pressed(KeyCode::A) {
-		impulse.x -= impulse_amount;
+	let Ok((id, mut dash, mut walk_animate, mut stamina)) = query.get_single_mut() else { return };
+	if !dash.cooldown.finished() {
+		return;
+	}
+	if !stamina.spend(DASH_STAMINA) {
+		ew_exhausted.send(events::ExhaustedEvent { id });
+		return;
 	}
 
-	if r_inputs.pressed(KeyCode::D) {
-		impulse.x += impulse_amount;
+	let mut direction = r_actions.move_axis;
+	if direction == Vec2::ZERO {
+		let Ok(weapon) = q_weapon.get_single() else { return };
+		direction = weapon.aim;
 	}
 
-	if r_inputs.pressed(KeyCode::W) {
-		impulse.y += impulse_amount;
+	dash.cooldown = Timer::from_seconds(DASH_COOLDOWN, TimerMode::Once);
+	let ghost_interval = walk_animate.ghosting_timer.as_ref().map(|timer| timer.duration());
+	if let Some(timer) = walk_animate.ghosting_timer.as_mut() {
+		timer.set_duration(BevyDuration::from_secs_f32(DASH_GHOST_INTERVAL));
 	}
 
-	if r_inputs.pressed(KeyCode::S) {
-		impulse.y -= impulse_amount;
+	commands.entity(id).insert(components::Dashing {
+		velocity: direction.normalize_or_zero() * DASH_DISTANCE / DASH_DURATION,
+		timer: Timer::from_seconds(DASH_DURATION, TimerMode::Once),
+		ghost_interval,
+	});
+	if dash.iframes {
+		commands.entity(id).insert(components::Invulnerable);
 	}
-		
-	velocity.v += impulse;
 }
 
-fn player_char_controls(
-	r_inputs: Res<Input<KeyCode>>,
-	mut query: Query<&mut components::Velocity, With<components::PlayerCharacter>>,
+fn dash_movement(
+	mut commands: Commands,
+	mut query: Query<(Entity, &mut components::Dashing, &mut components::Velocity, &mut components::WalkAnimate)>,
+	r_time: Res<Time>,
 ) {
-	let Ok(mut velocity) = query.get_single_mut() else { return };
-	let mut impulse = Vec2::ZERO; 
-	let speed = 120.0;
+	for (id, mut dashing, mut velocity, mut walk_animate) in query.iter_mut() {
+		//only the part of the frame spent dashing moves, so the distance is always the same
+		let before = dashing.timer.elapsed_secs();
+		dashing.timer.tick(r_time.delta());
+		let dt = r_time.delta_seconds();
+		if dt > 0.0 {
+			velocity.v += dashing.velocity * (dashing.timer.elapsed_secs() - before) / dt;
+		}
 
-	if r_inputs.pressed(KeyCode::A) {
-		impulse.x -= speed;
+		if !dashing.timer.finished() {
+			continue;
+		}
+		if let (Some(timer), Some(interval)) = (walk_animate.ghosting_timer.as_mut(), dashing.ghost_interval) {
+			timer.set_duration(interval);
+		}
+		commands.entity(id).remove::<(components::Dashing, components::Invulnerable)>();
 	}
+}
 
-	if r_inputs.pressed(KeyCode::D) {
-		impulse.x += speed;
+fn dash_cooldown(
+	mut query: Query<&mut components::DashAbility>,
+	r_time: Res<Time>,
+) {
+	for mut dash in query.iter_mut() {
+		dash.cooldown.tick(r_time.delta());
 	}
+}
 
-	if r_inputs.pressed(KeyCode::W) {
-		impulse.y += speed;
+//Fills up while the dash is cooling down
+fn dash_meter(
+	mut commands: Commands,
+	mut q_meter: Query<(Entity, &mut Style, &mut BackgroundColor, &components::DashMeter)>,
+	q_dash: Query<&components::DashAbility>,
+) {
+	for (id, mut style, mut color, meter) in q_meter.iter_mut() {
+		let Ok(dash) = q_dash.get(meter.id) else {
+			commands.entity(id).despawn_recursive();
+			continue;
+		};
+		let ready = dash.cooldown.finished();
+		style.width = Val::Px(DASH_METER_WIDTH * if ready { 1.0 } else { dash.cooldown.percent() });
+		*color = if ready { Color::WHITE.into() } else { Color::GRAY.into() };
 	}
+}
 
-	if r_inputs.pressed(KeyCode::S) {
-		impulse.y -= speed;
+fn player_char_controls(
+	r_actions: Res<actions::ActionState>,
+	mut query: Query<(&mut components::Velocity, Option<&components::SpeedBuff>), With<components::PlayerCharacter>>,
+	q_dashing: Query<(), (With<components::PlayerCharacter>, With<components::Dashing>)>,
+) {
+	//the dash takes over the movement until it ends
+	if !q_dashing.is_empty() {
+		return;
 	}
-	velocity.v += impulse;
+	let Ok((mut velocity, buff)) = query.get_single_mut() else { return };
+	let speed = 120.0 * buff.map_or(1.0, |buff| buff.multiplier);
+	velocity.v += r_actions.move_axis * speed;
 }
@@ -1,15 +1,15 @@
-use bevy::{
-	prelude::*, input::common_conditions::*
-};
+use bevy::prelude::*;
 
 use crate::components;
 use crate::system;
 use crate::events;
+use crate::inventory;
+use crate::block;
+use crate::projectile;
+use crate::actions;
 
 use crate::miscellaneous;
 
-const WEAPON_DAMAGE: f32 = 5.0;
-
 #[derive(Default, Clone)]
 pub struct PlayerWeaponPlugin;
 
@@ -18,21 +18,345 @@ impl Plugin for PlayerWeaponPlugin {
 		app
 		.add_systems(
 			Update,
-			player_weapon_attack.in_set(system::GameSet::CommandsAction).run_if(input_just_pressed(MouseButton::Left))
+			start_charge
+				.in_set(system::GameSet::SingleAction)
+				.run_if(actions::action_just_pressed(actions::Action::Attack))
+				.run_if(inventory::inventory_closed)
 		)
-		.add_systems(Update, player_weapon_transformation.in_set(system::GameSet::Apply))
+		.add_systems(
+			Update,
+			player_weapon_attack
+				.in_set(system::GameSet::CommandsAction)
+				.run_if(actions::action_just_released(actions::Action::Attack))
+				.run_if(inventory::inventory_closed)
+		)
+		.add_systems(Update, drop_charge.in_set(system::GameSet::SingleAction).run_if(not(inventory::inventory_closed)))
+		.add_systems(
+			Update,
+			secondary_attack
+				.in_set(system::GameSet::CommandsAction)
+				.run_if(actions::action_just_pressed(actions::Action::Secondary))
+				.run_if(inventory::inventory_closed)
+		)
+		.add_systems(
+			Update,
+			end_block
+				.in_set(system::GameSet::CommandsAction)
+				.run_if(actions::action_just_released(actions::Action::Secondary))
+		)
+		.add_systems(Update, switch_weapon.in_set(system::GameSet::Check))
+		.add_systems(Update, (weapon_cooldown, charge_up, drain_block, combo_timeout, focus_regen, (advance_swing, swing_hits).chain()).in_set(system::GameSet::ContinousAction))
+		.add_systems(Update, (player_weapon_transformation, charge_glow, combo_counter, focus_meter).in_set(system::GameSet::Apply))
 		.add_systems(Startup, setup);
 	}
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WeaponKind {
+	MeleeArc,
+	Thrust,
+	//shoots the projectile towards the aim, its damage and knockback are the projectile's own
+	Projectile(projectile::ProjectileDef),
+	AoeSlam,
+}
+
+//Area hit by an attack, relative to the player and the aim direction
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HitShape {
+	Arc    { radius: f32, half_angle: f32 },
+	Line   { length: f32, width: f32 },
+	Circle { radius: f32 },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WeaponVfx {
+	pub color: Color,
+	pub count: usize,
+	pub speed: f32,
+	pub scale: f32,
+	pub shake: f32,
+}
+
+//How the weapon moves through a swing, see swing_pose
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwingAnimation {
+	Slash,
+	Backslash,
+	Overhead,
+	Stab,
+	Shoot,
+	Slam,
+}
+
+//One attack of a combo chain, scaling the weapon's attack
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ComboStep {
+	//multiplier of the weapon's damage
+	pub damage   : f32,
+	//replaces the weapon's hitbox when set
+	pub hitbox   : Option<HitShape>,
+	pub animation: SwingAnimation,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SecondaryKind {
+	//throws a projectile towards the aim
+	Throw(projectile::ProjectileDef),
+	//held down, damage taken is reduced while blocking
+	Block { reduction: f32 },
+	//hits everything around the player at once
+	Shockwave { radius: f32, damage: f32, knockback: f32 },
+}
+
+//Attack on the right mouse button, paid for with focus
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SecondaryDef {
+	pub kind    : SecondaryKind,
+	//for blocks it starts once the block is let go
+	pub cooldown: f32,
+	pub cost    : f32,
+	pub color   : Color,
+}
+
+//How the weapon circles the player while not swinging
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WeaponOrbit {
+	pub radius   : f32,
+	//how fast the weapon turns towards the aim, 0 snaps straight to it
+	pub smoothing: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwingPhase {
+	Windup,
+	Active,
+	Recovery,
+}
+
+//How long each phase of a swing lasts, in seconds
+//Targets are only hit during the active phase
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SwingTiming {
+	pub windup  : f32,
+	pub active  : f32,
+	pub recovery: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct WeaponDef {
+	pub kind     : WeaponKind,
+	pub texture  : &'static str,
+	//direction the tip of the texture points at, in radians from the x axis
+	pub texture_angle: f32,
+	//ignored by projectile weapons, like knockback and hitbox
+	pub damage   : f32,
+	//counted from the start of the swing, it should not be shorter than the whole swing
+	pub cooldown : f32,
+	pub timing   : SwingTiming,
+	pub knockback: f32,
+	pub hitbox   : HitShape,
+	pub vfx      : WeaponVfx,
+	pub orbit    : WeaponOrbit,
+	//attacks done one after another go through these steps, empty for weapons without combos
+	pub combo    : &'static [ComboStep],
+	pub secondary: Option<SecondaryDef>,
+}
+
+//Used while no weapon is equipped
+pub const DEFAULT_WEAPON: WeaponDef = WeaponDef {
+	kind     : WeaponKind::MeleeArc,
+	texture  : "textures/rpg/props/generic-rpg-loot01.png",
+	texture_angle: std::f32::consts::FRAC_PI_4,
+	damage   : 5.0,
+	cooldown : 0.3,
+	timing   : SwingTiming { windup: 0.06, active: 0.1, recovery: 0.12 },
+	knockback: 100.0,
+	hitbox   : HitShape::Arc { radius: 36.0, half_angle: 0.8 },
+	vfx      : WeaponVfx { color: Color::WHITE, count: 50, speed: 100.0, scale: 1.0, shake: 1.0 },
+	orbit    : WeaponOrbit { radius: 30.0, smoothing: 20.0 },
+	combo    : &SLASH_COMBO,
+	secondary: Some(SecondaryDef {
+		kind    : SecondaryKind::Throw(projectile::ProjectileDef {
+			speed   : 350.0,
+			lifetime: 0.6,
+			damage  : 6.0,
+			size    : 6.0,
+			color   : Color::SILVER,
+			pierce  : 2,
+			homing  : 0.0,
+			on_hit  : projectile::OnHit::Knockback(60.0),
+		}),
+		cooldown: 0.8,
+		cost    : 20.0,
+		color   : Color::SILVER,
+	}),
+};
+
+pub const SLASH_COMBO: [ComboStep; 3] = [
+	ComboStep { damage: 1.0, hitbox: None, animation: SwingAnimation::Slash },
+	ComboStep { damage: 1.0, hitbox: None, animation: SwingAnimation::Backslash },
+	ComboStep { damage: 1.6, hitbox: Some(HitShape::Line { length: 44.0, width: 28.0 }), animation: SwingAnimation::Overhead },
+];
+
+//How far to each side of the aim a melee arc swings, in radians
+const SWING_ARC          : f32 = 1.2;
+//Seconds the attack has to be held for a full charge
+const FULL_CHARGE        : f32 = 0.8;
+//Multiplier of an attack released just before the charge is full
+const MAX_CHARGE_POWER   : f32 = 2.0;
+//A fully charged attack is a heavy attack, stronger and with a bigger hitbox
+const HEAVY_POWER        : f32 = 3.0;
+const HEAVY_HITBOX_SCALE : f32 = 1.5;
+const CHARGE_GLOW_SIZE   : f32 = 20.0;
+//Stamina spent per second of charging
+const CHARGE_STAMINA     : f32 = 40.0;
+//Stamina spent to raise a block, then per second of holding it
+const BLOCK_STAMINA      : f32 = 15.0;
+const BLOCK_DRAIN        : f32 = 20.0;
+//Focus is gained by landing hits and slowly by itself
+const FOCUS_PER_HIT      : f32 = 8.0;
+const FOCUS_REGEN        : f32 = 3.0;
+//Seconds after a swing to keep the combo going
+const COMBO_WINDOW       : f32 = 0.6;
+//The last hit of a combo shakes and pushes harder
+const FINAL_HIT_SHAKE    : f32 = 2.0;
+const FINAL_HIT_KNOCKBACK: f32 = 2.0;
+const WEAPON_ACTIONS     : [actions::Action; 4] = [
+	actions::Action::Weapon1,
+	actions::Action::Weapon2,
+	actions::Action::Weapon3,
+	actions::Action::Weapon4,
+];
+
+//Damage, knockback and effect multiplier of an attack charged for the given amount of seconds
+//Short taps are plain attacks
+pub fn charge_power(charge: f32) -> f32 {
+	if charge >= FULL_CHARGE {
+		return HEAVY_POWER;
+	}
+	1.0 + (MAX_CHARGE_POWER - 1.0) * (charge / FULL_CHARGE)
+}
+
+impl WeaponKind {
+	//Animation of attacks that are not part of a combo
+	pub fn animation(self) -> SwingAnimation {
+		match self {
+			WeaponKind::MeleeArc      => SwingAnimation::Slash,
+			WeaponKind::Thrust        => SwingAnimation::Stab,
+			WeaponKind::Projectile(_) => SwingAnimation::Shoot,
+			WeaponKind::AoeSlam       => SwingAnimation::Slam,
+		}
+	}
+}
+
+impl WeaponDef {
+	//Scales what the attack does to targets, what projectile weapons shoot included
+	fn scale_attack(&mut self, damage: f32, knockback: f32) {
+		self.damage *= damage;
+		self.knockback *= knockback;
+		if let WeaponKind::Projectile(projectile) = &mut self.kind {
+			*projectile = projectile.scaled(damage, knockback);
+		}
+	}
+
+	//Copy of the definition with the attack of a combo step
+	pub fn combo_step(self, step: ComboStep, last: bool) -> Self {
+		let mut def = self;
+		def.scale_attack(step.damage, if last { FINAL_HIT_KNOCKBACK } else { 1.0 });
+		def.hitbox = step.hitbox.unwrap_or(def.hitbox);
+		if last {
+			def.vfx.shake *= FINAL_HIT_SHAKE;
+		}
+		def
+	}
+
+	//Copy of the definition with the attack scaled by a charge power
+	pub fn charged(self, power: f32) -> Self {
+		let mut def = self;
+		def.scale_attack(power, power);
+		def.vfx.shake *= power;
+		def.vfx.scale *= power;
+		def.vfx.count = (def.vfx.count as f32 * power) as usize;
+		if power >= HEAVY_POWER {
+			def.hitbox = def.hitbox.scaled(HEAVY_HITBOX_SCALE);
+		}
+		def
+	}
+}
+
+impl SwingPhase {
+	fn duration(self, timing: &SwingTiming) -> f32 {
+		match self {
+			SwingPhase::Windup   => timing.windup,
+			SwingPhase::Active   => timing.active,
+			SwingPhase::Recovery => timing.recovery,
+		}
+	}
+}
+
+//Angle away from the aim and orbit radius multiplier of the weapon at a point of the swing
+//progress goes from 0 to 1 through each phase
+pub fn swing_pose(animation: SwingAnimation, phase: SwingPhase, progress: f32) -> (f32, f32) {
+	let lerp = |from: f32, to: f32| from + (to - from) * progress;
+	match (animation, phase) {
+		(SwingAnimation::Slash, SwingPhase::Windup)        => (lerp(0.0, -SWING_ARC), 1.0),
+		(SwingAnimation::Slash, SwingPhase::Active)        => (lerp(-SWING_ARC, SWING_ARC), 1.0),
+		(SwingAnimation::Slash, SwingPhase::Recovery)      => (lerp(SWING_ARC, 0.0), 1.0),
+		(SwingAnimation::Backslash, SwingPhase::Windup)    => (lerp(0.0, SWING_ARC), 1.0),
+		(SwingAnimation::Backslash, SwingPhase::Active)    => (lerp(SWING_ARC, -SWING_ARC), 1.0),
+		(SwingAnimation::Backslash, SwingPhase::Recovery)  => (lerp(-SWING_ARC, 0.0), 1.0),
+		(SwingAnimation::Overhead, SwingPhase::Windup)     => (0.0, lerp(1.0, 0.4)),
+		(SwingAnimation::Overhead, SwingPhase::Active)     => (0.0, lerp(0.4, 1.5)),
+		(SwingAnimation::Overhead, SwingPhase::Recovery)   => (0.0, lerp(1.5, 1.0)),
+		(SwingAnimation::Stab, SwingPhase::Windup)         => (0.0, lerp(1.0, 0.6)),
+		(SwingAnimation::Stab, SwingPhase::Active)         => (0.0, lerp(0.6, 1.8)),
+		(SwingAnimation::Stab, SwingPhase::Recovery)       => (0.0, lerp(1.8, 1.0)),
+		(SwingAnimation::Shoot, SwingPhase::Windup)        => (0.0, lerp(1.0, 0.8)),
+		(SwingAnimation::Shoot, SwingPhase::Active)        => (0.0, lerp(0.8, 1.0)),
+		(SwingAnimation::Shoot, SwingPhase::Recovery)      => (0.0, 1.0),
+		(SwingAnimation::Slam, SwingPhase::Windup)         => (0.0, lerp(1.0, 1.5)),
+		(SwingAnimation::Slam, SwingPhase::Active)         => (0.0, lerp(1.5, 0.3)),
+		(SwingAnimation::Slam, SwingPhase::Recovery)       => (0.0, lerp(0.3, 1.0)),
+	}
+}
+
+impl HitShape {
+	pub fn scaled(self, factor: f32) -> Self {
+		match self {
+			HitShape::Arc { radius, half_angle } => HitShape::Arc { radius: radius * factor, half_angle },
+			HitShape::Line { length, width }     => HitShape::Line { length: length * factor, width: width * factor },
+			HitShape::Circle { radius }          => HitShape::Circle { radius: radius * factor },
+		}
+	}
+
+	//Whether a target of the given radius at the offset from the origin is inside the shape
+	pub fn contains(self, offset: Vec2, direction: Vec2, target_radius: f32) -> bool {
+		match self {
+			HitShape::Arc { radius, half_angle } => {
+				let distance = offset.length();
+				if distance > radius + target_radius {
+					return false;
+				}
+				//targets overlapping the player are always hit
+				distance <= target_radius || direction.angle_between(offset).abs() <= half_angle
+			},
+			HitShape::Line { length, width } => {
+				let along = offset.dot(direction);
+				let across = offset.perp_dot(direction).abs();
+				along >= -target_radius && along <= length + target_radius && across <= width / 2.0 + target_radius
+			},
+			HitShape::Circle { radius } => offset.length() <= radius + target_radius,
+		}
+	}
+}
+
 fn setup(
 	mut commands: Commands,
 	r_asset_server: Res<AssetServer>
 ) {
-	let weapon_texture = r_asset_server.load("textures/rpg/props/generic-rpg-loot01.png");
+	let weapon_texture = r_asset_server.load(DEFAULT_WEAPON.texture);
 	commands.spawn((
-		components::PlayerWeapon { damage: WEAPON_DAMAGE },
-		components::Intersect { size: Vec2::splat(12.0) },
+		components::PlayerWeapon::new(DEFAULT_WEAPON),
 		SpriteBundle {
 			sprite: Sprite {
 				color: Color::rgba(1.0, 1.0, 1.0, 0.6),
@@ -43,80 +367,503 @@ fn setup(
 			texture: weapon_texture,
 			..default()
 		}
+	)).with_children(|parent| {
+		parent.spawn((
+			SpriteBundle {
+				sprite: Sprite {
+					custom_size: Some(Vec2::ZERO),
+					..default()
+				},
+				transform: Transform::from_xyz(0.0, 0.0, -1.0),
+				visibility: Visibility::Hidden,
+				..default()
+			},
+			components::ChargeGlow,
+		));
+	});
+
+	commands.spawn((
+		TextBundle::from_section(
+			"",
+			TextStyle {
+				font_size: 30.0,
+				color: Color::WHITE,
+				..default()
+			},
+		)
+		.with_style(Style {
+			position_type: PositionType::Absolute,
+			left: Val::Px(10.0),
+			bottom: Val::Px(45.0),
+			..default()
+		}),
+		components::ComboCounter::default(),
 	));
 }
 
+//The weapon actions pick an equipped weapon directly, next and previous cycle through them
+fn switch_weapon(
+	r_actions: Res<actions::ActionState>,
+	mut q_inventory: Query<&mut inventory::Inventory, With<components::PlayerCharacter>>,
+) {
+	let Ok(mut inventory) = q_inventory.get_single_mut() else { return };
+	let count = inventory.equipped.len();
+	if count == 0 {
+		return;
+	}
+
+	let mut active = inventory.active;
+	for (i, action) in WEAPON_ACTIONS.iter().enumerate() {
+		if r_actions.just_pressed(*action) && i < count {
+			active = i;
+		}
+	}
+	if r_actions.just_pressed(actions::Action::NextWeapon) {
+		active = (active + 1) % count;
+	} else if r_actions.just_pressed(actions::Action::PreviousWeapon) {
+		active = (active + count - 1) % count;
+	}
+
+	if active != inventory.active {
+		inventory.active = active;
+	}
+}
+
+fn weapon_cooldown(
+	mut q_weapon: Query<&mut components::PlayerWeapon>,
+	r_time: Res<Time>,
+) {
+	for mut weapon in q_weapon.iter_mut() {
+		weapon.cooldown.tick(r_time.delta());
+		weapon.secondary_cooldown.tick(r_time.delta());
+	}
+}
+
+fn start_charge(
+	mut q_weapon: Query<&mut components::PlayerWeapon, Without<components::Swing>>,
+) {
+	let Ok(mut weapon) = q_weapon.get_single_mut() else { return };
+	if weapon.cooldown.finished() {
+		weapon.charge = Some(0.0);
+	}
+}
+
+//A charge still held when the inventory opens would go off on the first release after it closes
+fn drop_charge(
+	mut q_weapon: Query<&mut components::PlayerWeapon>,
+) {
+	let Ok(mut weapon) = q_weapon.get_single_mut() else { return };
+	if weapon.charge.is_some() {
+		weapon.charge = None;
+	}
+}
+
+//Charging drains stamina, without it the charge stops growing
+fn charge_up(
+	mut q_weapon: Query<&mut components::PlayerWeapon>,
+	mut q_player: Query<(Entity, &mut components::Stamina), With<components::PlayerCharacter>>,
+	mut ew_exhausted: EventWriter<events::ExhaustedEvent>,
+	r_time: Res<Time>,
+) {
+	let Ok(mut weapon) = q_weapon.get_single_mut() else { return };
+	let Ok((player_id, mut stamina)) = q_player.get_single_mut() else { return };
+	let Some(charge) = weapon.charge.as_mut() else { return };
+	if *charge >= FULL_CHARGE {
+		return;
+	}
+
+	let dt = r_time.delta_seconds();
+	if !stamina.spend(CHARGE_STAMINA * dt) {
+		ew_exhausted.send(events::ExhaustedEvent { id: player_id });
+		return;
+	}
+	*charge = (*charge + dt).min(FULL_CHARGE);
+}
+
+//Releasing a charge only starts the swing, what it does happens once it reaches its active phase
 fn player_weapon_attack(
 	mut commands: Commands,
-	q_weapon: Query<(Entity, &Transform, &components::PlayerWeapon)>,
-	mut q_health: Query<&mut components::Health, Without<components::PlayerCharacter>>,
-	mut er_intersection: EventReader<events::IntersectEvent>,
+	mut q_weapon: Query<(Entity, &mut components::PlayerWeapon), Without<components::Swing>>,
+) {
+	let Ok((id, mut weapon)) = q_weapon.get_single_mut() else { return };
+	let Some(charge) = weapon.charge.take() else { return };
+
+	if !weapon.cooldown.finished() {
+		return;
+	}
+	let mut def = weapon.def;
+	let mut animation = def.kind.animation();
+	if !def.combo.is_empty() {
+		if weapon.combo >= def.combo.len() {
+			weapon.combo = 0;
+		}
+		let step = def.combo[weapon.combo];
+		weapon.combo += 1;
+		def = def.combo_step(step, weapon.combo == def.combo.len());
+		animation = step.animation;
+	}
+	let def = def.charged(charge_power(charge));
+	weapon.cooldown = Timer::from_seconds(def.cooldown, TimerMode::Once);
+
+	commands.entity(id).insert(components::Swing {
+		def,
+		phase: SwingPhase::Windup,
+		timer: Timer::from_seconds(def.timing.windup, TimerMode::Once),
+		direction: weapon.aim,
+		hit: Vec::new(),
+		heavy: charge >= FULL_CHARGE,
+		animation,
+	});
+}
+
+fn secondary_attack(
+	mut commands: Commands,
+	mut q_weapon: Query<(&Transform, &mut components::PlayerWeapon), Without<components::Swing>>,
+	mut q_player: Query<(Entity, &Transform, &mut components::Focus, &mut components::Stamina), With<components::PlayerCharacter>>,
+	mut q_target: Query<(Entity, &Transform, &components::Intersect, &mut components::Health), Without<components::PlayerCharacter>>,
 	mut ew_shake: EventWriter<events::ShakeEvent>,
+	mut ew_exhausted: EventWriter<events::ExhaustedEvent>,
 ) {
-	let Ok((id, transform, weapon)) = q_weapon.get_single() else {
-		return
-	};
+	let Ok((transform, mut weapon)) = q_weapon.get_single_mut() else { return };
+	let Ok((player_id, player_transform, mut focus, mut stamina)) = q_player.get_single_mut() else { return };
+	let Some(secondary) = weapon.def.secondary else { return };
 
+	if !weapon.secondary_cooldown.finished() || focus.current < secondary.cost {
+		return;
+	}
+	//blocks also need stamina
+	if matches!(secondary.kind, SecondaryKind::Block { .. }) && !stamina.spend(BLOCK_STAMINA) {
+		ew_exhausted.send(events::ExhaustedEvent { id: player_id });
+		return;
+	}
+	focus.current -= secondary.cost;
+
+	let burst_at = match secondary.kind {
+		SecondaryKind::Throw(_) => transform.translation,
+		_ => player_transform.translation,
+	};
 	miscellaneous::generic_particle_burst(
 		&mut commands,
-		transform.translation,
-		100.0,
-		0.5,
+		burst_at,
+		150.0,
+		0.4,
 		1.0,
-		50,
-		(Color::WHITE..Color::rgba(1.0, 1.0, 1.0, 0.0)).into()
+		40,
+		(secondary.color..secondary.color.with_a(0.0)).into()
 	);
 
-	ew_shake.send(events::ShakeEvent { intensity: 1.0 });
+	match secondary.kind {
+		SecondaryKind::Throw(projectile) => {
+			projectile::spawn_projectile(&mut commands, projectile, transform.translation, weapon.aim, false, None);
+		},
+		SecondaryKind::Block { reduction } => {
+			commands.entity(player_id).insert(components::Blocking {
+				reduction,
+				facing: weapon.aim,
+				parry: Timer::from_seconds(block::PARRY_WINDOW, TimerMode::Once),
+				cooldown: secondary.cooldown,
+			});
+			//the cooldown starts once the block is let go
+			return;
+		},
+		SecondaryKind::Shockwave { radius, damage, knockback } => {
+			let origin = player_transform.translation.truncate();
+			let hitbox = HitShape::Circle { radius };
+			for (id, target_transform, intersect, mut health) in q_target.iter_mut() {
+				let offset = target_transform.translation.truncate() - origin;
+				if !hitbox.contains(offset, weapon.aim, intersect.size.max_element() / 2.0) {
+					continue;
+				}
+				health.unapplied_damage += damage;
+				apply_knockback(&mut commands, id, offset.normalize_or_zero() * knockback);
+			}
+			ew_shake.send(events::ShakeEvent { intensity: 2.0 });
+		},
+	}
+	weapon.secondary_cooldown = Timer::from_seconds(secondary.cooldown, TimerMode::Once);
+}
 
-	for ev in er_intersection.read() {
-		let (a, b) = ev.ab;
+fn end_block(
+	mut commands: Commands,
+	mut q_weapon: Query<&mut components::PlayerWeapon>,
+	q_player: Query<(Entity, &components::Blocking), With<components::PlayerCharacter>>,
+) {
+	let Ok(mut weapon) = q_weapon.get_single_mut() else { return };
+	let Ok((player_id, blocking)) = q_player.get_single() else { return };
+	drop_block(&mut commands, player_id, blocking, &mut weapon);
+}
 
-		if id != a {
+//The weapon may have been switched while blocking, the cooldown is the one of the weapon that started it
+fn drop_block(commands: &mut Commands, player_id: Entity, blocking: &components::Blocking, weapon: &mut components::PlayerWeapon) {
+	commands.entity(player_id).remove::<components::Blocking>();
+	weapon.secondary_cooldown = Timer::from_seconds(blocking.cooldown, TimerMode::Once);
+}
+
+//Holding a block drains stamina, the block drops once there is not enough left
+fn drain_block(
+	mut commands: Commands,
+	mut q_weapon: Query<&mut components::PlayerWeapon>,
+	mut q_player: Query<(Entity, &components::Blocking, &mut components::Stamina), With<components::PlayerCharacter>>,
+	mut ew_exhausted: EventWriter<events::ExhaustedEvent>,
+	r_time: Res<Time>,
+) {
+	let Ok(mut weapon) = q_weapon.get_single_mut() else { return };
+	let Ok((player_id, blocking, mut stamina)) = q_player.get_single_mut() else { return };
+
+	if stamina.spend(BLOCK_DRAIN * r_time.delta_seconds()) {
+		return;
+	}
+	ew_exhausted.send(events::ExhaustedEvent { id: player_id });
+	drop_block(&mut commands, player_id, blocking, &mut weapon);
+}
+
+fn focus_regen(
+	mut q_focus: Query<&mut components::Focus>,
+	r_time: Res<Time>,
+) {
+	for mut focus in q_focus.iter_mut() {
+		focus.gain(FOCUS_REGEN * r_time.delta_seconds());
+	}
+}
+
+fn focus_meter(
+	mut commands: Commands,
+	mut q_style: Query<(Entity, &mut Style, &components::FocusMeter)>,
+	q_focus: Query<&components::Focus>,
+) {
+	for (id, mut style, meter) in q_style.iter_mut() {
+		let Ok(focus) = q_focus.get(meter.id) else {
+			commands.entity(id).despawn_recursive();
 			continue;
 		};
+		style.height = Val::Px(focus.current * 2.0);
+	}
+}
+
+//The combo is lost when the window after a swing runs out or when the player gets hit
+//The window waits while swinging or charging the next attack
+fn combo_timeout(
+	mut q_weapon: Query<(&mut components::PlayerWeapon, Has<components::Swing>)>,
+	q_player: Query<Entity, With<components::PlayerCharacter>>,
+	mut er_damaged: EventReader<events::DamagedEvent>,
+	r_time: Res<Time>,
+) {
+	let Ok((mut weapon, swinging)) = q_weapon.get_single_mut() else { return };
+	let Ok(player_id) = q_player.get_single() else { return };
+
+	if er_damaged.read().any(|event| event.target == player_id) {
+		weapon.combo = 0;
+	}
+	if swinging || weapon.charge.is_some() {
+		return;
+	}
+	weapon.combo_window.tick(r_time.delta());
+	if weapon.combo_window.just_finished() {
+		weapon.combo = 0;
+	}
+}
+
+fn combo_counter(
+	q_weapon: Query<&components::PlayerWeapon>,
+	mut q_text: Query<(&mut Text, &mut Visibility, &mut components::ComboCounter)>,
+) {
+	let Ok(weapon) = q_weapon.get_single() else { return };
+	let Ok((mut text, mut visibility, mut counter)) = q_text.get_single_mut() else { return };
+	//the weapon changes every frame, the text only when the combo does
+	if counter.shown == weapon.combo {
+		return;
+	}
 
-		let Ok(mut health) = q_health.get_mut(b) else {
+	counter.shown = weapon.combo;
+	*visibility = if weapon.combo > 0 { Visibility::Inherited } else { Visibility::Hidden };
+	text.sections[0].value = format!("Combo x{}", weapon.combo);
+}
+
+//Grows while the attack charges and flashes once the charge is full
+fn charge_glow(
+	q_weapon: Query<&components::PlayerWeapon>,
+	mut q_glow: Query<(&mut Sprite, &mut Visibility), With<components::ChargeGlow>>,
+	r_time: Res<Time>,
+) {
+	let Ok(weapon) = q_weapon.get_single() else { return };
+	let Ok((mut sprite, mut visibility)) = q_glow.get_single_mut() else { return };
+
+	let Some(charge) = weapon.charge else {
+		*visibility = Visibility::Hidden;
+		return;
+	};
+	*visibility = Visibility::Inherited;
+
+	let ratio = charge / FULL_CHARGE;
+	sprite.custom_size = Some(Vec2::splat(CHARGE_GLOW_SIZE * ratio));
+	sprite.color = if ratio >= 1.0 {
+		let pulse = (r_time.elapsed_seconds() * 20.0).sin() * 0.5 + 0.5;
+		Color::rgba(1.0, 1.0, 0.6, 0.5 + pulse * 0.5)
+	} else {
+		Color::rgba(1.0, 0.8, 0.3, 0.3 + ratio * 0.4)
+	};
+}
+
+//Moves swings through their phases, the effects of the attack start with the active phase
+fn advance_swing(
+	mut commands: Commands,
+	mut q_weapon: Query<(Entity, &Transform, &mut components::Swing, &mut components::PlayerWeapon)>,
+	q_player: Query<&Transform, With<components::PlayerCharacter>>,
+	mut ew_shake: EventWriter<events::ShakeEvent>,
+	r_time: Res<Time>,
+) {
+	let Ok((id, transform, mut swing, mut weapon)) = q_weapon.get_single_mut() else { return };
+	let Ok(player_transform) = q_player.get_single() else { return };
+
+	swing.timer.tick(r_time.delta());
+	if !swing.timer.finished() {
+		return;
+	}
+
+	let def = swing.def;
+	let next = match swing.phase {
+		SwingPhase::Windup   => SwingPhase::Active,
+		SwingPhase::Active   => SwingPhase::Recovery,
+		SwingPhase::Recovery => {
+			commands.entity(id).remove::<components::Swing>();
+			weapon.combo_window = Timer::from_seconds(COMBO_WINDOW, TimerMode::Once);
+			return;
+		},
+	};
+	swing.phase = next;
+	swing.timer = Timer::from_seconds(next.duration(&def.timing), TimerMode::Once);
+
+	if next != SwingPhase::Active {
+		return;
+	}
+
+	//AoE slams burst around the player, the rest at the weapon
+	let burst_at = match def.kind {
+		WeaponKind::AoeSlam => player_transform.translation,
+		_ => transform.translation,
+	};
+	miscellaneous::generic_particle_burst(
+		&mut commands,
+		burst_at,
+		def.vfx.speed,
+		0.5,
+		def.vfx.scale,
+		def.vfx.count,
+		(def.vfx.color..def.vfx.color.with_a(0.0)).into()
+	);
+	ew_shake.send(events::ShakeEvent { intensity: def.vfx.shake });
+
+	//Heavy attacks also send a ring out from the player
+	if swing.heavy {
+		miscellaneous::generic_particle_burst(
+			&mut commands,
+			player_transform.translation,
+			def.vfx.speed * 2.0,
+			0.4,
+			def.vfx.scale,
+			def.vfx.count,
+			(Color::rgba(1.0, 1.0, 0.6, 1.0)..Color::rgba(1.0, 0.8, 0.3, 0.0)).into()
+		);
+	}
+
+	if let WeaponKind::Projectile(projectile) = def.kind {
+		projectile::spawn_projectile(&mut commands, projectile, transform.translation, swing.direction, false, None);
+	}
+}
+
+//Everything inside the hitbox while the swing is active is damaged, once per swing
+fn swing_hits(
+	mut commands: Commands,
+	mut q_weapon: Query<&mut components::Swing>,
+	mut q_player: Query<(&Transform, &mut components::Focus), With<components::PlayerCharacter>>,
+	mut q_target: Query<(Entity, &Transform, &components::Intersect, &mut components::Health), Without<components::PlayerCharacter>>,
+) {
+	let Ok(mut swing) = q_weapon.get_single_mut() else { return };
+	let Ok((player_transform, mut focus)) = q_player.get_single_mut() else { return };
+
+	if swing.phase != SwingPhase::Active || matches!(swing.def.kind, WeaponKind::Projectile(_)) {
+		return;
+	}
+
+	let def = swing.def;
+	let origin = player_transform.translation.truncate();
+	for (id, target_transform, intersect, mut health) in q_target.iter_mut() {
+		if swing.hit.contains(&id) {
 			continue;
-		};
+		}
+		let offset = target_transform.translation.truncate() - origin;
+		let target_radius = intersect.size.max_element() / 2.0;
+		if !def.hitbox.contains(offset, swing.direction, target_radius) {
+			continue;
+		}
 
 		//Once all the checks are done the damage is applied
-		health.unapplied_damage += weapon.damage;
+		health.unapplied_damage += def.damage;
+		apply_knockback(&mut commands, id, offset.normalize_or_zero() * def.knockback);
+		focus.gain(FOCUS_PER_HIT);
+		swing.hit.push(id);
+	}
+}
+
+fn apply_knockback(commands: &mut Commands, id: Entity, v: Vec2) {
+	if v != Vec2::ZERO {
+		commands.entity(id).insert(components::Knockback { v, decay: miscellaneous::KNOCKBACK_DECAY });
 	}
 }
 
 fn player_weapon_transformation(
-	mut q_weapon: Query<&mut Transform, With<components::PlayerWeapon>>,
+	mut q_weapon: Query<(&mut Transform, &mut Sprite, &mut components::PlayerWeapon, Option<&components::Swing>)>,
 	q_player:     Query<&Transform, (With<components::PlayerCharacter>, Without<components::PlayerWeapon>)>,
-	q_window: Query<&Window, With<bevy::window::PrimaryWindow>>,
-	q_camera: Query<(&Camera, &GlobalTransform)>,
+	r_actions: Res<actions::ActionState>,
+	r_time: Res<Time>,
 ) {
 	let(
-		Ok(mut weapon_transform), 
+		Ok((mut weapon_transform, mut sprite, mut weapon, swing)),
 		Ok(player_transform),
-		Ok(window),
-		Ok((camera, gt_camera))
 		) = (
 			q_weapon.get_single_mut(),
 			q_player.get_single(),
-			q_window.get_single(),
-			q_camera.get_single()
 	)
 	else {
 		return
 	};
 
-	let Some(cursor_pos) = window.cursor_position()
-		.and_then(|cursor| camera.viewport_to_world_2d(gt_camera, cursor))
-	else { return };
-
 	let mut translation = player_transform.translation;
 	translation.z = 950.0;
-	let v = cursor_pos - translation.truncate();
-	let b = v.normalize();
-	let rot = Quat::from_rotation_arc_2d(Vec2::Y, b).normalize();
-	let direction = rot * Vec3::Y;
-	let translation = translation + (direction * 30.0);
 
-	weapon_transform.translation = translation;
+	//Without anything to aim at the last aim is kept
+	let aim = r_actions.aim_direction.or_else(|| {
+		r_actions.aim_point.map(|aim_point| (aim_point - translation.truncate()).normalize_or_zero())
+	});
+	if let Some(aim) = aim.filter(|aim| *aim != Vec2::ZERO) {
+		weapon.aim = aim;
+	}
+
+	let orbit = weapon.def.orbit;
+	let turn = if orbit.smoothing > 0.0 {
+		1.0 - (-orbit.smoothing * r_time.delta_seconds()).exp()
+	} else {
+		1.0
+	};
+	weapon.facing = Vec2::from_angle(weapon.facing.angle_between(weapon.aim) * turn).rotate(weapon.facing);
+
+	//While swinging the weapon follows the swing instead of the cursor
+	let (def, direction, reach) = match swing {
+		Some(swing) => {
+			let (angle, reach) = swing_pose(swing.animation, swing.phase, swing.timer.percent());
+			(swing.def, Vec2::from_angle(angle).rotate(swing.direction), reach)
+		},
+		None => (weapon.def, weapon.facing, 1.0),
+	};
+	if swing.is_some() {
+		//so the weapon turns back from where the swing left it
+		weapon.facing = direction;
+	}
+	weapon_transform.translation = translation + (direction * def.orbit.radius * reach).extend(0.0);
+
+	//On the left the texture is mirrored so it is not held upside down
+	let flip = direction.x < 0.0;
+	let tip = if flip { std::f32::consts::PI - def.texture_angle } else { def.texture_angle };
+	sprite.flip_x = flip;
+	weapon_transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x) - tip);
 }
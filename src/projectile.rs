@@ -0,0 +1,201 @@
+use bevy::prelude::*;
+
+use crate::block;
+use crate::components;
+use crate::events;
+use crate::map;
+use crate::miscellaneous;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Update, projectile_hits.in_set(system::GameSet::CommandsAction))
+		.add_systems(Update, steer_projectiles.in_set(system::GameSet::ContinousAction));
+	}
+}
+
+//Homing projectiles only look for targets this close
+const HOMING_RANGE: f32 = 150.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OnHit {
+	Knockback(f32),
+	//damages everything around the hit, the hit target included
+	Explode { radius: f32, damage: f32 },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ProjectileDef {
+	pub speed   : f32,
+	pub lifetime: f32,
+	pub damage  : f32,
+	pub size    : f32,
+	pub color   : Color,
+	//how many targets it goes through before breaking
+	pub pierce  : u32,
+	//how fast it turns towards the closest target, 0 flies straight
+	pub homing  : f32,
+	pub on_hit  : OnHit,
+}
+
+impl ProjectileDef {
+	//Copy with the damage of the hit and of explosions, and the knockback scaled
+	pub fn scaled(self, damage: f32, knockback: f32) -> Self {
+		let mut def = self;
+		def.damage *= damage;
+		def.on_hit = match def.on_hit {
+			OnHit::Knockback(force) => OnHit::Knockback(force * knockback),
+			OnHit::Explode { radius, damage: explosion } => OnHit::Explode { radius, damage: explosion * damage },
+		};
+		def
+	}
+}
+
+//Hostile projectiles only hit the player, the rest hit anything else with health
+pub fn spawn_projectile(
+	commands: &mut Commands,
+	def: ProjectileDef,
+	position: Vec3,
+	direction: Vec2,
+	hostile: bool,
+	owner: Option<Entity>,
+) -> Entity {
+	commands.spawn((
+		SpriteBundle {
+			sprite: Sprite {
+				color: def.color,
+				custom_size: Some(Vec2::splat(def.size)),
+				..default()
+			},
+			transform: Transform::from_translation(position),
+			..default()
+		},
+		components::Projectile {
+			def,
+			direction: direction.normalize_or_zero(),
+			hostile,
+			owner,
+			pierced: Vec::new(),
+		},
+		components::Velocity::default(),
+		components::Intersect { size: Vec2::splat(def.size) },
+		components::DespawnerTimer::new(bevy::utils::Duration::from_secs_f32(def.lifetime)),
+	)).id()
+}
+
+fn impact_burst(commands: &mut Commands, translation: Vec3, def: &ProjectileDef) {
+	let (speed, count) = match def.on_hit {
+		OnHit::Explode { radius, .. } => (radius * 3.0, 80),
+		_ => (80.0, 15),
+	};
+	miscellaneous::generic_particle_burst(
+		commands,
+		translation,
+		speed,
+		0.3,
+		1.0,
+		count,
+		(def.color..def.color.with_a(0.0)).into()
+	);
+}
+
+//Turns homing projectiles towards their closest target and breaks projectiles on blocking tiles
+fn steer_projectiles(
+	mut commands: Commands,
+	r_tile_metadata: Res<map::TileMetadata>,
+	mut q_projectile: Query<(Entity, &mut components::Projectile, &mut components::Velocity, &Transform)>,
+	q_target: Query<(&Transform, Has<components::PlayerCharacter>), With<components::Health>>,
+	r_time: Res<Time>,
+) {
+	for (id, mut projectile, mut velocity, transform) in q_projectile.iter_mut() {
+		let position = transform.translation.truncate();
+		if !r_tile_metadata.is_walkable(position) {
+			impact_burst(&mut commands, transform.translation, &projectile.def);
+			commands.entity(id).despawn_recursive();
+			continue;
+		}
+
+		if projectile.def.homing > 0.0 {
+			let closest = q_target.iter()
+				.filter(|(_, is_player)| *is_player == projectile.hostile)
+				.map(|(target, _)| target.translation.truncate() - position)
+				.filter(|offset| offset.length() <= HOMING_RANGE)
+				.min_by(|a, b| a.length().total_cmp(&b.length()));
+
+			if let Some(offset) = closest {
+				let turn = projectile.def.homing * r_time.delta_seconds();
+				projectile.direction = (projectile.direction + offset.normalize_or_zero() * turn).normalize_or_zero();
+			}
+		}
+
+		velocity.v += projectile.direction * projectile.def.speed;
+	}
+}
+
+fn projectile_hits(
+	mut commands: Commands,
+	mut er_intersect: EventReader<events::IntersectEvent>,
+	mut q_projectile: Query<(&mut components::Projectile, &Transform)>,
+	mut q_health: Query<(&mut components::Health, &Transform, Has<components::PlayerCharacter>), Without<components::Projectile>>,
+	q_blocking: Query<&components::Blocking>,
+	mut ew_shake: EventWriter<events::ShakeEvent>,
+	mut ew_parry: EventWriter<events::ParryEvent>,
+) {
+	let mut spent = Vec::new();
+	for event in er_intersect.read() {
+		let (a, b) = event.ab;
+		if spent.contains(&a) {
+			continue;
+		}
+		let Ok((mut projectile, transform)) = q_projectile.get_mut(a) else { continue };
+		if projectile.pierced.contains(&b) {
+			continue;
+		}
+		let Ok((mut health, target_transform, is_player)) = q_health.get_mut(b) else { continue };
+		if is_player != projectile.hostile {
+			continue;
+		}
+
+		let def = projectile.def;
+		let position = target_transform.translation.truncate();
+		let multiplier = match block::block_outcome(q_blocking.get(b).ok(), position, transform.translation.truncate()) {
+			//parried projectiles are sent back at whoever shot them
+			block::BlockOutcome::Parried => {
+				ew_parry.send(events::ParryEvent { attacker: projectile.owner, position });
+				projectile.hostile = !projectile.hostile;
+				projectile.direction = -projectile.direction;
+				projectile.pierced.clear();
+				continue;
+			},
+			block::BlockOutcome::Blocked(multiplier) => multiplier,
+			block::BlockOutcome::Unblocked => 1.0,
+		};
+		health.unapplied_damage += def.damage * multiplier;
+		projectile.pierced.push(b);
+		impact_burst(&mut commands, transform.translation, &def);
+		ew_shake.send(events::ShakeEvent { intensity: 0.5 });
+
+		match def.on_hit {
+			OnHit::Knockback(strength) => {
+				let v = (target_transform.translation - transform.translation).truncate().normalize_or_zero() * strength;
+				commands.entity(b).insert(components::Knockback { v, decay: miscellaneous::KNOCKBACK_DECAY });
+			},
+			OnHit::Explode { radius, damage } => {
+				let center = transform.translation.truncate();
+				for (mut health, target_transform, is_player) in q_health.iter_mut() {
+					if is_player == projectile.hostile && target_transform.translation.truncate().distance(center) <= radius {
+						health.unapplied_damage += damage;
+					}
+				}
+			},
+		}
+
+		if projectile.pierced.len() as u32 > def.pierce {
+			spent.push(a);
+			commands.entity(a).despawn_recursive();
+		}
+	}
+}
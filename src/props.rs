@@ -0,0 +1,323 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use rand::prelude::*;
+
+use std::borrow::Cow;
+
+use crate::chest;
+use crate::components;
+use crate::events;
+use crate::inventory;
+use crate::loot;
+use crate::map;
+use crate::miscellaneous;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct PropsPlugin;
+
+impl Plugin for PropsPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Startup, place_props.after(map::generate_map).after(chest::spawn_chests))
+		.add_systems(Update, break_destructibles.in_set(system::GameSet::CommandsAction));
+	}
+}
+
+//Kept just inside BOUNDS * 2.0 in miscellaneous, props outside of it could never be reached
+const PLACEMENT_EXTENT: f32 = 330.0;
+//Nothing is placed this close to where the player and the boss spawn
+const SPAWN_CLEARANCE : f32 = 70.0;
+const SPAWN_POINTS    : [Vec2; 2] = [Vec2::ZERO, Vec2::new(0.0, 200.0)];
+const FENCE_PIECE_SIZE: f32 = 16.0;
+const PROPS_PATH      : &str = "textures/rpg/props/";
+
+struct PropDef {
+	textures : &'static [&'static str],
+	//amount of props for every 100x100 world units
+	density  : f32,
+	//minimum distance between this prop and any other one
+	spacing  : f32,
+	//collider placed at the base of the prop, it also blocks the tiles below it
+	collider : Option<Vec2>,
+	//props that can be broken need a collider so the weapon can hit them
+	breakable: Option<Breakable>,
+}
+
+struct Breakable {
+	health    : f32,
+	drop_table: &'static loot::DropTable,
+}
+
+const BARREL_DROPS: loot::DropTable = loot::DropTable {
+	rolls: 1,
+	entries: Cow::Borrowed(&[
+		(3.0, None),
+		(2.0, Some(loot::LootKind::Item(inventory::ItemId::HealthPotion))),
+		(2.0, Some(loot::LootKind::Currency)),
+		(1.0, Some(loot::LootKind::Item(inventory::ItemId::SpeedPotion))),
+	]),
+};
+
+const CRATE_DROPS: loot::DropTable = loot::DropTable {
+	rolls: 2,
+	entries: Cow::Borrowed(&[
+		(3.0, None),
+		(3.0, Some(loot::LootKind::Currency)),
+		(1.0, Some(loot::LootKind::Item(inventory::ItemId::HealthPotion))),
+		(1.0, Some(loot::LootKind::Item(inventory::ItemId::Sword))),
+	]),
+};
+
+const PROP_DEFS: [PropDef; 6] = [
+	PropDef {
+		textures: &["generic-rpg-tree01.png", "generic-rpg-tree02.png"],
+		density: 0.5,
+		spacing: 50.0,
+		collider: Some(Vec2::new(14.0, 8.0)),
+		breakable: None,
+	},
+	PropDef {
+		textures: &[
+			"generic-rpg-rock01.png", "generic-rpg-rock02.png", "generic-rpg-rock03.png",
+			"generic-rpg-rock04.png", "generic-rpg-rock05.png", "generic-rpg-rock06.png",
+		],
+		density: 0.4,
+		spacing: 30.0,
+		collider: Some(Vec2::new(16.0, 8.0)),
+		breakable: None,
+	},
+	PropDef {
+		textures: &["generic-rpg-barrel01.png", "generic-rpg-barrel02.png", "generic-rpg-barrel03.png"],
+		density: 0.2,
+		spacing: 24.0,
+		collider: Some(Vec2::new(12.0, 8.0)),
+		breakable: Some(Breakable { health: 10.0, drop_table: &BARREL_DROPS }),
+	},
+	PropDef {
+		textures: &["generic-rpg-crate01.png", "generic-rpg-crate02.png", "generic-rpg-crate03.png"],
+		density: 0.2,
+		spacing: 24.0,
+		collider: Some(Vec2::new(14.0, 8.0)),
+		breakable: Some(Breakable { health: 15.0, drop_table: &CRATE_DROPS }),
+	},
+	PropDef {
+		textures: &[
+			"generic-rpg-flower01.png", "generic-rpg-flower02.png", "generic-rpg-flower03.png",
+			"generic-rpg-grass01.png", "generic-rpg-grass02.png",
+		],
+		density: 1.2,
+		spacing: 12.0,
+		collider: None,
+		breakable: None,
+	},
+	PropDef {
+		textures: &["generic-rpg-house-inn.png"],
+		density: 0.02,
+		spacing: 120.0,
+		collider: Some(Vec2::new(64.0, 24.0)),
+		breakable: None,
+	},
+];
+
+//Pieces of generic-rpg-fence-complete split in parts, see spawn_fence_pen
+const FENCE_TOP_LEFT    : &str = "generic-rpg-fence01.png";
+const FENCE_TOP         : [&str; 3] = ["generic-rpg-fence02.png", "generic-rpg-fence03.png", "generic-rpg-fence04.png"];
+const FENCE_TOP_RIGHT   : &str = "generic-rpg-fence05.png";
+const FENCE_RIGHT       : [&str; 2] = ["generic-rpg-fence06.png", "generic-rpg-fence07.png"];
+const FENCE_BOTTOM_RIGHT: &str = "generic-rpg-fence08.png";
+const FENCE_BOTTOM      : [&str; 3] = ["generic-rpg-fence09.png", "generic-rpg-fence10.png", "generic-rpg-fence11.png"];
+const FENCE_BOTTOM_LEFT : &str = "generic-rpg-fence12.png";
+const FENCE_LEFT        : [&str; 2] = ["generic-rpg-fence13.png", "generic-rpg-fence14.png"];
+const FENCE_PENS        : usize = 3;
+
+//Circle that is already taken by a placed prop
+struct Occupied {
+	position: Vec2,
+	radius  : f32,
+}
+
+fn is_free(occupied: &[Occupied], position: Vec2, radius: f32) -> bool {
+	SPAWN_POINTS.iter().all(|spawn| spawn.distance(position) > SPAWN_CLEARANCE + radius)
+		&& occupied.iter().all(|o| o.position.distance(position) > o.radius.max(radius))
+}
+
+//Props only stand on plain grass
+fn is_grass_area(tile_metadata: &map::TileMetadata, position: Vec2, size: Vec2) -> bool {
+	let half = size / 2.0;
+	[Vec2::ZERO, Vec2::new(-half.x, -half.y), Vec2::new(half.x, -half.y), Vec2::new(-half.x, half.y), half]
+		.iter()
+		.all(|offset| {
+			tile_metadata.properties_at(position + *offset)
+				.is_some_and(|properties| properties.surface == map::SurfaceKind::Grass && properties.walkable)
+		})
+}
+
+fn spawn_prop(
+	commands: &mut Commands,
+	tile_metadata: &mut map::TileMetadata,
+	texture: Handle<Image>,
+	position: Vec2,
+	anchor: Anchor,
+	collider: Option<Vec2>,
+	breakable: Option<&Breakable>,
+) -> Entity {
+	let mut prop = commands.spawn((
+		SpriteBundle {
+			sprite: Sprite { anchor, ..default() },
+			transform: Transform::from_translation(position.extend(0.0)),
+			texture,
+			..default()
+		},
+		components::DepthSort { base: 900.0 },
+	));
+
+	if let Some(size) = collider {
+		prop.insert(components::Intersect { size });
+		tile_metadata.set_area_blocked(position, size, true);
+	}
+
+	if let Some(breakable) = breakable {
+		prop.insert((
+			components::Health::new(breakable.health),
+			components::Destructible { drop_table: breakable.drop_table },
+		));
+	}
+	prop.id()
+}
+
+//Fence pens are rectangles of fence pieces, the bottom side is left with a gap to walk in
+fn spawn_fence_pen(
+	commands: &mut Commands,
+	r_asset_server: &AssetServer,
+	tile_metadata: &mut map::TileMetadata,
+	rng: &mut ThreadRng,
+	origin: Vec2,
+	width: usize,
+	height: usize,
+) {
+	let gap = rng.gen_range(1..width - 1);
+	for x in 0..width {
+		for y in 0..height {
+			let texture = match (x, y) {
+				(0, 0)                                        => FENCE_BOTTOM_LEFT,
+				(0, y) if y == height - 1                     => FENCE_TOP_LEFT,
+				(x, 0) if x == width - 1                      => FENCE_BOTTOM_RIGHT,
+				(x, y) if x == width - 1 && y == height - 1   => FENCE_TOP_RIGHT,
+				(0, _)                                        => FENCE_LEFT[y % FENCE_LEFT.len()],
+				(x, _) if x == width - 1                      => FENCE_RIGHT[y % FENCE_RIGHT.len()],
+				(_, 0) if x == gap                            => continue,
+				(_, 0)                                        => FENCE_BOTTOM[x % FENCE_BOTTOM.len()],
+				(_, y) if y == height - 1                     => FENCE_TOP[x % FENCE_TOP.len()],
+				_                                             => continue,
+			};
+			let position = origin + Vec2::new(x as f32, y as f32) * FENCE_PIECE_SIZE;
+			spawn_prop(
+				commands,
+				tile_metadata,
+				r_asset_server.load(format!("{PROPS_PATH}{texture}")),
+				position,
+				Anchor::Center,
+				Some(Vec2::splat(FENCE_PIECE_SIZE * 0.5)),
+				None,
+			);
+		}
+	}
+}
+
+fn random_position(rng: &mut ThreadRng) -> Vec2 {
+	Vec2::new(
+		rng.gen_range(-PLACEMENT_EXTENT..PLACEMENT_EXTENT),
+		rng.gen_range(-PLACEMENT_EXTENT..PLACEMENT_EXTENT),
+	)
+}
+
+pub fn place_props(
+	mut commands: Commands,
+	r_asset_server: Res<AssetServer>,
+	mut rm_tile_metadata: ResMut<map::TileMetadata>,
+) {
+	let mut rng = rand::thread_rng();
+	let mut occupied: Vec<Occupied> = Vec::new();
+
+	//Fences go first since they need the most room
+	for _ in 0..FENCE_PENS {
+		let width  = rng.gen_range(4..8);
+		let height = rng.gen_range(3..6);
+		let size = Vec2::new(width as f32, height as f32) * FENCE_PIECE_SIZE;
+		//some attempts are allowed to fail, the map is random anyway
+		for _ in 0..20 {
+			let origin = random_position(&mut rng);
+			let center = origin + size / 2.0;
+			let radius = size.length() / 2.0;
+			if !is_free(&occupied, center, radius) || !is_grass_area(&rm_tile_metadata, center, size) {
+				continue;
+			}
+			spawn_fence_pen(&mut commands, &r_asset_server, &mut rm_tile_metadata, &mut rng, origin, width, height);
+			occupied.push(Occupied { position: center, radius });
+			break;
+		}
+	}
+
+	let area = (PLACEMENT_EXTENT * 2.0) * (PLACEMENT_EXTENT * 2.0);
+	for def in PROP_DEFS.iter() {
+		let count = (area / (100.0 * 100.0) * def.density).round().max(1.0) as usize;
+		for _ in 0..count {
+			for _ in 0..20 {
+				let position = random_position(&mut rng);
+				let footprint = def.collider.unwrap_or(Vec2::splat(4.0));
+				if !is_free(&occupied, position, def.spacing) || !is_grass_area(&rm_tile_metadata, position, footprint) {
+					continue;
+				}
+				let texture = def.textures[rng.gen_range(0..def.textures.len())];
+				spawn_prop(
+					&mut commands,
+					&mut rm_tile_metadata,
+					r_asset_server.load(format!("{PROPS_PATH}{texture}")),
+					position,
+					Anchor::BottomCenter,
+					def.collider,
+					def.breakable.as_ref(),
+				);
+				occupied.push(Occupied { position, radius: def.spacing });
+				break;
+			}
+		}
+	}
+}
+
+//Broken props free the tiles they were blocking, burst into splinters and roll their drop table
+fn break_destructibles(
+	mut commands: Commands,
+	mut rm_tile_metadata: ResMut<map::TileMetadata>,
+	q_destructible: Query<(Entity, &Transform, &components::Health, &components::Destructible, Option<&components::Intersect>)>,
+	mut ew_loot_drop: EventWriter<events::LootDropEvent>,
+	mut ew_shake: EventWriter<events::ShakeEvent>,
+) {
+	let mut rng = rand::thread_rng();
+	for (id, transform, health, destructible, intersect) in q_destructible.iter() {
+		if health.current > 0.0 {
+			continue;
+		}
+
+		let position = transform.translation.truncate();
+		if let Some(intersect) = intersect {
+			rm_tile_metadata.set_area_blocked(position, intersect.size, false);
+		}
+
+		miscellaneous::generic_particle_burst(
+			&mut commands,
+			transform.translation,
+			150.0,
+			0.5,
+			2.0,
+			30,
+			(Color::rgb(0.55, 0.35, 0.2)..Color::rgba(0.55, 0.35, 0.2, 0.0)).into()
+		);
+		ew_shake.send(events::ShakeEvent { intensity: 0.5 });
+
+		for kind in destructible.drop_table.roll(&mut rng) {
+			ew_loot_drop.send(events::LootDropEvent { kind, position });
+		}
+		commands.entity(id).despawn_recursive();
+	}
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Clone, Default)]
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(PreStartup, load_save)
+		.add_systems(Last, write_save.run_if(changed_since_load::<SaveData>));
+	}
+}
+
+const SAVE_PATH: &str = "save.ron";
+
+//Everything about the world that outlives a single run
+#[derive(Clone, Default, Resource, Serialize, Deserialize)]
+pub struct SaveData {
+	pub opened_chests: Vec<u32>,
+}
+
+//A missing or broken save just starts a new one
+fn load_save(mut commands: Commands) {
+	let save = std::fs::read_to_string(SAVE_PATH)
+		.ok()
+		.and_then(|contents| ron::from_str::<SaveData>(&contents).ok())
+		.unwrap_or_default();
+	commands.insert_resource(save);
+}
+
+fn write_save(r_save: Res<SaveData>) {
+	write_ron(SAVE_PATH, &*r_save);
+}
+
+//Run condition for writing a loaded resource back to disk
+//Loading inserts the resource, that alone is not worth writing the file again
+pub fn changed_since_load<T: Resource>(r_resource: Res<T>) -> bool {
+	r_resource.is_changed() && !r_resource.is_added()
+}
+
+//Failing to write only costs the changes, so it is logged rather than stopping the game
+pub fn write_ron<T: Serialize>(path: impl AsRef<Path>, value: &T) {
+	let path = path.as_ref();
+	let contents = match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+		Ok(contents) => contents,
+		Err(error) => {
+			warn!("could not serialize {}: {error}", path.display());
+			return;
+		}
+	};
+
+	if let Err(error) = std::fs::write(path, contents) {
+		warn!("could not write {}: {error}", path.display());
+	}
+}
@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+
+use crate::components;
+use crate::events;
+use crate::miscellaneous;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct StaminaPlugin;
+
+impl Plugin for StaminaPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Update, stamina_regen.in_set(system::GameSet::ContinousAction))
+		.add_systems(Update, (exhausted_feedback, stamina_meter).chain().in_set(system::GameSet::Apply));
+	}
+}
+
+//Seconds the meter flashes for when an action is refused
+const EXHAUSTED_FLASH: f32 = 0.3;
+
+//Waits for the regen delay after stamina was spent, then fills it back up
+fn stamina_regen(
+	mut q_stamina: Query<&mut components::Stamina>,
+	r_time: Res<Time>,
+) {
+	for mut stamina in q_stamina.iter_mut() {
+		stamina.delay.tick(r_time.delta());
+		if stamina.delay.finished() {
+			let regen = stamina.regen * r_time.delta_seconds();
+			stamina.current = (stamina.current + regen).min(stamina.max);
+		}
+	}
+}
+
+//Flashes the meter and puffs out some dust, the puff is skipped while the meter is still flashing
+fn exhausted_feedback(
+	mut commands: Commands,
+	mut er_exhausted: EventReader<events::ExhaustedEvent>,
+	mut q_meter: Query<&mut components::StaminaMeter>,
+	q_transform: Query<&Transform>,
+) {
+	for event in er_exhausted.read() {
+		for mut meter in q_meter.iter_mut() {
+			if meter.id != event.id || !meter.flash.finished() {
+				continue;
+			}
+			meter.flash = Timer::from_seconds(EXHAUSTED_FLASH, TimerMode::Once);
+
+			let Ok(transform) = q_transform.get(event.id) else { continue };
+			miscellaneous::generic_particle_burst(
+				&mut commands,
+				transform.translation,
+				40.0,
+				0.4,
+				1.0,
+				15,
+				(Color::GRAY..Color::rgba(0.5, 0.5, 0.5, 0.0)).into()
+			);
+		}
+	}
+}
+
+fn stamina_meter(
+	mut commands: Commands,
+	mut q_meter: Query<(Entity, &mut Style, &mut BackgroundColor, &mut components::StaminaMeter)>,
+	q_stamina: Query<&components::Stamina>,
+	r_time: Res<Time>,
+) {
+	for (id, mut style, mut color, mut meter) in q_meter.iter_mut() {
+		let Ok(stamina) = q_stamina.get(meter.id) else {
+			commands.entity(id).despawn_recursive();
+			continue;
+		};
+		meter.flash.tick(r_time.delta());
+		style.height = Val::Px(stamina.current * 3.0);
+		*color = if meter.flash.finished() { Color::YELLOW.into() } else { Color::RED.into() };
+	}
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::broadphase;
+use crate::components;
+use crate::enemy::Behaviour;
+use crate::map;
+use crate::pathfinding;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Update, steer.in_set(system::GameSet::ContinousAction));
+	}
+}
+
+//Every behaviour asks for a direction no longer than 1, they are added up by weight
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+pub enum SteeringBehaviour {
+	//straight at the player, or around obstacles along the flow field
+	Seek,
+	//like seek, slowing down within `radius` of the player
+	Arrive { radius: f32 },
+	//away from the player while it is within `radius`
+	Flee { radius: f32 },
+	//away from other enemies within `radius`, harder the closer they are
+	Separation { radius: f32 },
+	//turns aside when the tile `lookahead` ahead can't be walked on
+	ObstacleAvoidance { lookahead: f32 },
+	//drifts around, turning by up to `jitter` radians a second
+	Wander { jitter: f32 },
+}
+
+//Enemies right on top of each other are pushed apart in a direction picked from their id
+fn separation(
+	r_spatial_hash: &broadphase::SpatialHash,
+	q_enemy: &Query<(), With<components::Enemy>>,
+	id: Entity,
+	position: Vec2,
+	radius: f32,
+) -> Vec2 {
+	r_spatial_hash.neighbours(position, radius)
+		.filter(|neighbour| neighbour.id != id && q_enemy.contains(neighbour.id))
+		.map(|neighbour| {
+			let away = position - neighbour.position;
+			let direction = away.try_normalize().unwrap_or_else(|| Vec2::from_angle(id.index() as f32));
+			direction * (1.0 - away.length() / radius)
+		})
+		.sum::<Vec2>()
+		.clamp_length_max(1.0)
+}
+
+//Turns to whichever side is clear when the way ahead is blocked
+fn avoid_obstacles(r_tile_metadata: &map::TileMetadata, position: Vec2, heading: Vec2, lookahead: f32) -> Vec2 {
+	if heading == Vec2::ZERO || r_tile_metadata.is_walkable(position + heading * lookahead) {
+		return Vec2::ZERO;
+	}
+	let side = heading.perp();
+	if r_tile_metadata.is_walkable(position + side * lookahead) {
+		side
+	} else if r_tile_metadata.is_walkable(position - side * lookahead) {
+		-side
+	} else {
+		-heading
+	}
+}
+
+//Guards that are not chasing anyone still keep apart and wander around
+fn steer(
+	mut q_steering: Query<(Entity, &mut components::Velocity, &Transform, &components::Enemy, &mut components::Steering), Without<components::Stagger>>,
+	q_enemy: Query<(), With<components::Enemy>>,
+	q_player_transform: Query<&Transform, (With<components::PlayerCharacter>, Without<components::Enemy>)>,
+	r_spatial_hash: Res<broadphase::SpatialHash>,
+	r_flow_field: Res<pathfinding::FlowField>,
+	r_tile_metadata: Res<map::TileMetadata>,
+	r_time: Res<Time>,
+) {
+	let Ok(target_transform) = q_player_transform.get_single() else { return };
+	let target = target_transform.translation.truncate();
+	let mut rng = rand::thread_rng();
+
+	for (id, mut velocity, transform, enemy, mut steering) in q_steering.iter_mut() {
+		let position = transform.translation.truncate();
+		let distance = position.distance(target);
+		let engaged = match enemy.behaviour {
+			Behaviour::Chase => true,
+			Behaviour::Guard { range } => distance <= range,
+			Behaviour::Boss => false,
+		};
+
+		let steering = &mut *steering;
+		let mut desired = Vec2::ZERO;
+		for (weight, behaviour) in steering.behaviours.iter() {
+			let force = match *behaviour {
+				SteeringBehaviour::Seek | SteeringBehaviour::Arrive { .. } if !engaged => Vec2::ZERO,
+				SteeringBehaviour::Seek => r_flow_field.direction(&r_tile_metadata, position, target),
+				SteeringBehaviour::Arrive { radius } => {
+					r_flow_field.direction(&r_tile_metadata, position, target) * (distance / radius).min(1.0)
+				},
+				SteeringBehaviour::Flee { radius } => {
+					if !engaged || distance >= radius {
+						Vec2::ZERO
+					} else {
+						(position - target).normalize_or_zero() * (1.0 - distance / radius)
+					}
+				},
+				SteeringBehaviour::Separation { radius } => separation(&r_spatial_hash, &q_enemy, id, position, radius),
+				SteeringBehaviour::ObstacleAvoidance { lookahead } => avoid_obstacles(&r_tile_metadata, position, steering.heading, lookahead),
+				SteeringBehaviour::Wander { jitter } => {
+					steering.wander_angle += rng.gen_range(-jitter..=jitter) * r_time.delta_seconds();
+					Vec2::from_angle(steering.wander_angle)
+				},
+			};
+			desired += force * *weight;
+		}
+
+		let desired = desired.clamp_length_max(1.0);
+		if let Some(heading) = desired.try_normalize() {
+			steering.heading = heading;
+		}
+		velocity.v += desired * enemy.speed;
+	}
+}
@@ -0,0 +1,145 @@
+use bevy::{
+	prelude::*, render::mesh::{Indices, PrimitiveTopology}, sprite::MaterialMesh2dBundle
+};
+
+use crate::components;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct TelegraphPlugin;
+
+impl Plugin for TelegraphPlugin {
+	fn build(&self, app: &mut App) {
+		app.add_systems(Update, build_telegraphs.in_set(system::GameSet::SingleAction))
+		.add_systems(Update, fade_telegraphs.in_set(system::GameSet::Apply))
+		.add_systems(Update, clear_telegraphs.in_set(system::GameSet::Despawn));
+	}
+}
+
+//Above the ground tiles, below everything that is depth sorted
+const TELEGRAPH_Z        : f32 = 1.0;
+//How opaque a marker is once the windup is over
+const TELEGRAPH_MAX_ALPHA: f32 = 0.5;
+const CONE_SEGMENTS      : usize = 16;
+
+//Shapes are laid out from the position along the direction
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TelegraphShape {
+	Line { length: f32, width: f32 },
+	Circle { radius: f32 },
+	//angle is the full opening of the cone, in radians
+	Cone { radius: f32, angle: f32 },
+}
+
+//Warns about an attack for `windup` seconds, then goes away when the attack lands
+//A zero direction, like a target standing on the attacker, lays the shape out along +x
+pub fn spawn_telegraph(
+	commands: &mut Commands,
+	shape: TelegraphShape,
+	position: Vec2,
+	direction: Vec2,
+	windup: f32,
+	color: Color,
+	owner: Option<Entity>,
+) -> Entity {
+	commands.spawn((
+		SpatialBundle {
+			transform: Transform::from_translation(position.extend(TELEGRAPH_Z))
+				.with_rotation(Quat::from_rotation_z(Vec2::X.angle_between(direction.try_normalize().unwrap_or(Vec2::X)))),
+			..default()
+		},
+		components::Telegraph {
+			shape,
+			timer: Timer::from_seconds(windup, TimerMode::Once),
+			color,
+			owner,
+		},
+	)).id()
+}
+
+//Outline of a cone pointing along x, starting and ending at the tip
+pub fn cone_points(radius: f32, angle: f32, segments: usize) -> Vec<Vec2> {
+	let mut points = vec![Vec2::ZERO];
+	points.extend((0..=segments).map(|i| {
+		let t = i as f32 / segments as f32;
+		Vec2::from_angle(-angle / 2.0 + angle * t) * radius
+	}));
+	points
+}
+
+fn telegraph_mesh(shape: TelegraphShape) -> Mesh {
+	match shape {
+		TelegraphShape::Line { length, width } => {
+			//the quad is centered, it is moved so it starts at the origin
+			let mut mesh = Mesh::from(shape::Quad::new(Vec2::new(length, width)));
+			if let Some(bevy::render::mesh::VertexAttributeValues::Float32x3(positions)) = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+				for position in positions.iter_mut() {
+					position[0] += length / 2.0;
+				}
+			}
+			mesh
+		},
+		TelegraphShape::Circle { radius } => Mesh::from(shape::Circle::new(radius)),
+		TelegraphShape::Cone { radius, angle } => {
+			let points = cone_points(radius, angle, CONE_SEGMENTS);
+			let positions: Vec<[f32; 3]> = points.iter().map(|point| [point.x, point.y, 0.0]).collect();
+			let normals = vec![[0.0, 0.0, 1.0]; points.len()];
+			let uvs: Vec<[f32; 2]> = points.iter().map(|point| [point.x / radius, point.y / radius]).collect();
+			let indices = (1..points.len() as u32 - 1).flat_map(|i| [0, i, i + 1]).collect();
+
+			let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+			mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+			mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+			mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+			mesh.set_indices(Some(Indices::U32(indices)));
+			mesh
+		},
+	}
+}
+
+//Every marker gets its own material so they can fade separately
+fn build_telegraphs(
+	mut commands: Commands,
+	q_telegraph: Query<(Entity, &components::Telegraph, &Transform), Added<components::Telegraph>>,
+	mut rm_meshes: ResMut<Assets<Mesh>>,
+	mut rm_materials: ResMut<Assets<ColorMaterial>>,
+) {
+	for (id, telegraph, transform) in q_telegraph.iter() {
+		commands.entity(id).insert(MaterialMesh2dBundle {
+			mesh: rm_meshes.add(telegraph_mesh(telegraph.shape)).into(),
+			material: rm_materials.add(ColorMaterial::from(telegraph.color.with_a(0.0))),
+			transform: *transform,
+			..default()
+		});
+	}
+}
+
+//Markers wait along with a staggered owner, its windup goes on once the stagger is over
+fn fade_telegraphs(
+	mut q_telegraph: Query<(&mut components::Telegraph, &Handle<ColorMaterial>)>,
+	q_staggered: Query<(), With<components::Stagger>>,
+	mut rm_materials: ResMut<Assets<ColorMaterial>>,
+	r_time: Res<Time>,
+) {
+	for (mut telegraph, material) in q_telegraph.iter_mut() {
+		if !telegraph.owner.is_some_and(|owner| q_staggered.contains(owner)) {
+			telegraph.timer.tick(r_time.delta());
+		}
+		let Some(material) = rm_materials.get_mut(material) else { continue };
+		material.color = telegraph.color.with_a(TELEGRAPH_MAX_ALPHA * telegraph.timer.percent());
+	}
+}
+
+//Markers go away once the attack lands or whoever made them is gone
+fn clear_telegraphs(
+	mut commands: Commands,
+	q_telegraph: Query<(Entity, &components::Telegraph)>,
+	q_owner: Query<(), Without<components::Telegraph>>,
+) {
+	for (id, telegraph) in q_telegraph.iter() {
+		let orphaned = telegraph.owner.is_some_and(|owner| !q_owner.contains(owner));
+		if telegraph.timer.finished() || orphaned {
+			commands.entity(id).despawn_recursive();
+		}
+	}
+}
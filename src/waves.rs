@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::Deserialize;
+
+use std::path::{Path, PathBuf};
+
+use crate::components;
+use crate::enemy;
+use crate::events;
+use crate::map;
+use crate::miscellaneous;
+use crate::system;
+
+#[derive(Clone, Default)]
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+	fn build(&self, app: &mut App) {
+		app.insert_resource(Waves {
+			path: miscellaneous::asset_folder(app).join(WAVES_FILE),
+			..default()
+		})
+		.init_resource::<WaveState>()
+		.add_systems(PreStartup, load_waves)
+		//clearing is checked first, so enemies spawned this frame are already counted next frame
+		.add_systems(Update, (wave_clear, wave_start, wave_spawn).chain().in_set(system::GameSet::CommandsAction))
+		.add_systems(Update, wave_banner.in_set(system::GameSet::Apply));
+	}
+}
+
+//All waves in the order they are fought, the boss comes after the last one
+const WAVES_FILE      : &str = "waves.ron";
+const BANNER_DURATION : f32 = 2.0;
+//Tries at finding a walkable spot before giving up on it
+const SPAWN_ATTEMPTS  : usize = 10;
+
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+pub enum SpawnRegion {
+	Ring { center: Vec2, inner: f32, outer: f32 },
+	Rect { min: Vec2, max: Vec2 },
+	//the rect or its mirror across the vertical axis, picked evenly
+	Mirrored { min: Vec2, max: Vec2 },
+}
+
+impl SpawnRegion {
+	pub fn sample(&self, rng: &mut impl Rng) -> Vec2 {
+		match *self {
+			SpawnRegion::Ring { center, inner, outer } => {
+				center + Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)) * rng.gen_range(inner..=outer)
+			},
+			SpawnRegion::Rect { min, max } => Vec2::new(rng.gen_range(min.x..=max.x), rng.gen_range(min.y..=max.y)),
+			SpawnRegion::Mirrored { min, max } => {
+				let position = SpawnRegion::Rect { min, max }.sample(rng);
+				if rng.gen() { position * Vec2::new(-1.0, 1.0) } else { position }
+			},
+		}
+	}
+}
+
+//`count` enemies of one archetype, one every `delay` seconds
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct SpawnGroup {
+	pub archetype: String,
+	pub count    : u32,
+	pub delay    : f32,
+	pub region   : SpawnRegion,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WaveDef {
+	//seconds to wait before the wave starts
+	pub start_delay: f32,
+	pub groups     : Vec<SpawnGroup>,
+}
+
+#[derive(Clone, Default, Resource)]
+pub struct Waves {
+	path : PathBuf,
+	waves: Vec<WaveDef>,
+}
+
+#[derive(Clone)]
+struct GroupProgress {
+	group    : SpawnGroup,
+	remaining: u32,
+	timer    : Timer,
+}
+
+#[derive(Clone, Default, Resource)]
+struct WaveState {
+	wave   : usize,
+	//counts down to the start of the wave
+	timer  : Timer,
+	started: bool,
+	groups : Vec<GroupProgress>,
+}
+
+fn read_waves(path: &Path) -> Result<Vec<WaveDef>, String> {
+	let contents = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+	ron::from_str(&contents).map_err(|error| error.to_string())
+}
+
+//A broken file leaves no waves to fight
+fn load_waves(mut rm_waves: ResMut<Waves>, mut rm_state: ResMut<WaveState>) {
+	match read_waves(&rm_waves.path) {
+		Ok(waves) => rm_waves.waves = waves,
+		Err(error) => warn!("could not load waves from {}: {error}", rm_waves.path.display()),
+	}
+	if let Some(first) = rm_waves.waves.first() {
+		rm_state.timer = Timer::from_seconds(first.start_delay, TimerMode::Once);
+	}
+}
+
+fn wave_start(
+	mut rm_state: ResMut<WaveState>,
+	r_waves: Res<Waves>,
+	mut ew_started: EventWriter<events::WaveStartedEvent>,
+	r_time: Res<Time>,
+) {
+	let state = rm_state.as_mut();
+	if state.started || state.wave >= r_waves.waves.len() {
+		return;
+	}
+	if !state.timer.tick(r_time.delta()).finished() {
+		return;
+	}
+
+	state.started = true;
+	state.groups = r_waves.waves[state.wave].groups.iter().map(|group| GroupProgress {
+		group: group.clone(),
+		remaining: group.count,
+		//the first enemy of every group shows up right away
+		timer: Timer::from_seconds(0.0, TimerMode::Once),
+	}).collect();
+	ew_started.send(events::WaveStartedEvent { wave: state.wave });
+}
+
+//Spots that can't be walked on are skipped, it is tried again next frame
+fn wave_spawn(
+	mut commands: Commands,
+	mut rm_state: ResMut<WaveState>,
+	r_archetypes: Res<enemy::EnemyArchetypes>,
+	r_tile_metadata: Res<map::TileMetadata>,
+	r_time: Res<Time>,
+) {
+	if !rm_state.started {
+		return;
+	}
+
+	let mut rng = rand::thread_rng();
+	for progress in rm_state.groups.iter_mut() {
+		if progress.remaining == 0 || !progress.timer.tick(r_time.delta()).finished() {
+			continue;
+		}
+		let position = (0..SPAWN_ATTEMPTS)
+			.map(|_| progress.group.region.sample(&mut rng))
+			.find(|position| r_tile_metadata.is_walkable(*position));
+		let Some(position) = position else { continue };
+
+		//an archetype that can't be spawned is tried again after the delay, the wave waits for it
+		if enemy::spawn_enemy(&mut commands, &r_archetypes, &progress.group.archetype, position).is_some() {
+			progress.remaining -= 1;
+		}
+		progress.timer = Timer::from_seconds(progress.group.delay, TimerMode::Once);
+	}
+}
+
+fn wave_clear(
+	mut rm_state: ResMut<WaveState>,
+	r_waves: Res<Waves>,
+	q_enemy: Query<(), With<components::Enemy>>,
+	mut ew_cleared: EventWriter<events::WaveClearedEvent>,
+) {
+	let state = rm_state.as_mut();
+	if !state.started || state.groups.iter().any(|progress| progress.remaining > 0) || !q_enemy.is_empty() {
+		return;
+	}
+
+	let last = state.wave + 1 == r_waves.waves.len();
+	ew_cleared.send(events::WaveClearedEvent { wave: state.wave, last });
+	state.wave += 1;
+	state.started = false;
+	if let Some(next) = r_waves.waves.get(state.wave) {
+		state.timer = Timer::from_seconds(next.start_delay, TimerMode::Once);
+	}
+}
+
+fn wave_banner(
+	mut commands: Commands,
+	mut er_started: EventReader<events::WaveStartedEvent>,
+	mut er_cleared: EventReader<events::WaveClearedEvent>,
+) {
+	for event in er_started.read() {
+		miscellaneous::spawn_banner(&mut commands, format!("Wave {}", event.wave + 1), Color::WHITE, BANNER_DURATION);
+	}
+	//the boss brings its own banner after the last one
+	for event in er_cleared.read().filter(|event| !event.last) {
+		miscellaneous::spawn_banner(&mut commands, format!("Wave {} cleared", event.wave + 1), Color::WHITE, BANNER_DURATION);
+	}
+}